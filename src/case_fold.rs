@@ -0,0 +1,132 @@
+use opt_struct::OptVec;
+use caseless::Caseless;
+
+use crate::{
+    SourceEvent,
+    source::{FlatMapper, Mapped},
+};
+
+/*
+
+   Full case folding (CaseFolding.txt, C + F mappings): https://www.unicode.org/reports/tr44/#CaseFolding.txt
+
+      'ß' => "ss", 'İ' => "i\u{307}", 'Σ' => 'σ', ...
+
+   Tailoring:
+
+      turkic:      'I' => 'ı', 'İ' => 'i'  (tr, az)
+      final sigma: 'Σ','σ','ς' at the end of a word (preceded by a letter, not followed by a letter) => 'ς'
+
+   Every output char gets the Local of the input char it came from.
+
+*/
+
+#[derive(Debug,Clone,Copy,Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct CaseFold {
+    turkic: bool,
+    final_sigma: bool,
+    after_letter: bool,
+}
+impl CaseFold {
+    pub fn new() -> CaseFold {
+        CaseFold::default()
+    }
+    pub fn turkic(mut self) -> CaseFold {
+        self.turkic = true;
+        self
+    }
+    pub fn final_sigma(mut self) -> CaseFold {
+        self.final_sigma = true;
+        self
+    }
+
+    fn fold(&self, c: char) -> OptVec<SourceEvent> {
+        match c {
+            'I' if self.turkic => OptVec::One(SourceEvent::Char('ı')),
+            'İ' if self.turkic => OptVec::One(SourceEvent::Char('i')),
+            _ => std::iter::once(c).default_case_fold().map(SourceEvent::Char).collect(),
+        }
+    }
+}
+impl FlatMapper for CaseFold {
+    fn map(&mut self, pending: &[SourceEvent], eof: bool) -> Mapped {
+        let events = match pending[0] {
+            SourceEvent::Breaker(_) => {
+                self.after_letter = false;
+                OptVec::One(pending[0])
+            },
+            SourceEvent::Char(c @ ('Σ' | 'σ' | 'ς')) if self.final_sigma => {
+                let next_letter = match pending.get(1) {
+                    Some(SourceEvent::Char(n)) => n.is_alphabetic(),
+                    Some(SourceEvent::Breaker(_)) => false,
+                    None if eof => false,
+                    None => return Mapped::Wait,
+                };
+                let sigma = match self.after_letter && !next_letter {
+                    true => 'ς',
+                    false => 'σ',
+                };
+                self.after_letter = c.is_alphabetic();
+                OptVec::One(SourceEvent::Char(sigma))
+            },
+            SourceEvent::Char(c) => {
+                self.after_letter = c.is_alphabetic();
+                self.fold(c)
+            },
+        };
+        Mapped::Emit { consumed: 1, events }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn folded<S: Source>(mut src: S) -> String {
+        let mut s = String::new();
+        while let Some(local_se) = src.next_char().unwrap() {
+            match local_se.data() {
+                SourceEvent::Char(c) => s.push(*c),
+                SourceEvent::Breaker(_) => s.push(' '),
+            }
+        }
+        s
+    }
+
+    #[test]
+    fn full() {
+        let mut src = "ßİΣ".into_source().case_fold();
+
+        let mut res_iter = [
+            SourceEvent::Char('s').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 2 }),
+            SourceEvent::Char('s').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 2 }),
+            SourceEvent::Char('i').localize(Snip { offset: 1, length: 1 },Snip { offset: 2, length: 2 }),
+            SourceEvent::Char('\u{307}').localize(Snip { offset: 1, length: 1 },Snip { offset: 2, length: 2 }),
+            SourceEvent::Char('σ').localize(Snip { offset: 2, length: 1 },Snip { offset: 4, length: 2 }),
+        ].into_iter();
+
+        while let Some(local_event) = src.next_char().unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Source: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+    }
+
+    #[test]
+    fn tailoring() {
+        let text = "IŞIK İz ΟΔΟΣ. Σ ΣΑΣ";
+        assert_eq!(folded(text.into_source().into_separator().case_fold()),"işik i̇z οδοσ. σ σασ");
+        assert_eq!(folded(text.into_source().into_separator().case_fold_with(CaseFold::new().turkic())),"ışık iz οδοσ. σ σασ");
+        assert_eq!(folded(text.into_source().into_separator().case_fold_with(CaseFold::new().final_sigma())),"işik i̇z οδος. σ σας");
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{
+    Source, Processed,
+    Shift, SourceExt,
+};
+
+/*
+
+   Checkpoints of parsers: the parser state (state machine, buffered events) is saved
+   between two events and restored later into a parser created with the same builder
+   (builder options are not a part of the checkpoint).
+
+   Snapshot adds the Processed counters of the source, so the job can be restarted
+   from a source positioned at the same byte offset with identical events and offsets:
+
+      let snapshot = Snapshot::take(&parser,&src)?;
+      ... serialize (feature "serde"), restart ...
+      file.seek(SeekFrom::Start(snapshot.processed().bytes as u64))?;
+      let mut parser = tagger::Builder::new().create();
+      let mut src = snapshot.restore(&mut parser,ReaderSource::new(file));
+
+   A source chain (src.pipe(a).pipe(b), adapters included) is its own checkpoint, it is rebuilt
+   over the source positioned at the same offset and restored:
+
+      let snapshot = Snapshot::take(&src,&src)?;
+      ...
+      let mut src = snapshot.offset(ReaderSource::new(file)).into_separator().pipe(a).pipe(b);
+      snapshot.restore_source(&mut src);
+
+   Positional sources (StrSource, ReaderSource) have an empty state, adapters save their buffered events
+   (IntoSeparator, MergeSeparator, FlatMap, Normalize). FlatMap saves its mapper too (it can be stateful,
+   as final sigma of CaseFold), so the mapper must be Clone. Filters (closures) are not a part of
+   the checkpoint, as builder options. Chain, FieldsSource, encoding sources and ParserSource
+   have no checkpoints.
+
+   A checkpoint is None if the parser can't be resumed (its source is already over or failed,
+   PipedWith in the middle of the events of a Parsed).
+
+*/
+
+pub trait Checkpoint {
+    type State;
+
+    fn checkpoint(&self) -> Option<Self::State>;
+    fn restore(&mut self, state: Self::State);
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Snapshot<T> {
+    processed: Processed,
+    state: T,
+}
+impl<T> Snapshot<T> {
+    pub fn take<P,S>(parser: &P, src: &S) -> Option<Snapshot<T>>
+    where P: Checkpoint<State = T>,
+          S: Source
+    {
+        Some(Snapshot {
+            processed: src.processed(),
+            state: parser.checkpoint()?,
+        })
+    }
+    pub fn processed(&self) -> Processed {
+        self.processed
+    }
+    // src has to start at the processed().bytes of the original input
+    pub fn restore<P,S>(self, parser: &mut P, src: S) -> Shift<S>
+    where P: Checkpoint<State = T>,
+          S: Source
+    {
+        parser.restore(self.state);
+        src.with_offset(self.processed)
+    }
+    // src has to start at the processed().bytes of the original input
+    pub fn offset<S: Source>(&self, src: S) -> Shift<S> {
+        src.with_offset(self.processed)
+    }
+    // source chain built over Snapshot::offset
+    pub fn restore_source<S>(self, src: &mut S)
+    where S: Checkpoint<State = T>
+    {
+        src.restore(self.state);
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod opt_vec {
+    use opt_struct::OptVec;
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+    pub fn serialize<T: Serialize, S: Serializer>(v: &OptVec<T>, serializer: S) -> Result<S::Ok,S::Error> {
+        serializer.collect_seq(v)
+    }
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<OptVec<T>,D::Error> {
+        Vec::deserialize(deserializer).map(OptVec::from)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn run<P: Parser, S: Source>(parser: &mut P, src: &mut S, limit: Option<usize>) -> Vec<Local<ParserEvent<P::Data>>> {
+        let mut res = Vec::new();
+        while limit.map(|limit| res.len() < limit).unwrap_or(true) {
+            match parser.next_event(src).unwrap() {
+                Some(local_event) => res.push(local_event),
+                None => break,
+            }
+        }
+        res
+    }
+
+    fn drain<S: Source>(src: &mut S) -> Vec<Local<SourceEvent>> {
+        let mut res = Vec::new();
+        while let Some(local_se) = src.next_char().unwrap() {
+            res.push(local_se);
+        }
+        res
+    }
+
+    #[test]
+    fn tagger() {
+        let text = "Ёж <a href=\"x\">&amp;</a> <p>Привет, <b>мир</b>!</p>";
+        let builder = tagger::Builder::auto_detect().with_attribute(tagger::TagName::A,"href");
+        let res = run(&mut builder.clone().create(),&mut text.into_source(),None);
+
+        for limit in [1,2,5,7,9] {
+            let mut parser = builder.clone().create();
+            let mut src = text.into_source();
+            let mut events = run(&mut parser,&mut src,Some(limit));
+            let snapshot = Snapshot::take(&parser,&src).unwrap();
+
+            let mut parser = builder.clone().create();
+            let bytes = snapshot.processed().bytes;
+            let mut src = snapshot.restore(&mut parser,text[bytes ..].into_source());
+            events.extend(run(&mut parser,&mut src,None));
+            assert_eq!(events,res);
+        }
+    }
+
+    #[test]
+    fn graphemes() {
+        let text = "e\u{301}👨\u{200d}👩 🇷🇺🇺🇸 Ёж!";
+        let res = run(&mut GraphemeParser::new(),&mut text.into_source().into_separator(),None);
+
+        for limit in 1 .. res.len() {
+            let mut parser = GraphemeParser::new();
+            let mut src = text.into_source().into_separator();
+            let mut events = run(&mut parser,&mut src,Some(limit));
+            let snapshot = Snapshot::take(&parser,&src).unwrap();
+
+            let mut parser = GraphemeParser::new();
+            let bytes = snapshot.processed().bytes;
+            let mut src = snapshot.restore(&mut parser,text[bytes ..].into_source().into_separator());
+            events.extend(run(&mut parser,&mut src,None));
+            assert_eq!(events,res);
+        }
+    }
+
+    #[test]
+    fn piped() {
+        let text = "a &amp; b &nbsp;&quot &#128175;!";
+        let res = {
+            let mut src = text.into_source().pipe(entities::Builder::new().create().into_piped());
+            let mut res = Vec::new();
+            while let Some(local_se) = src.next_char().unwrap() {
+                res.push(local_se);
+            }
+            res
+        };
+
+        for limit in [2,3,6,8] {
+            let mut parser = entities::Builder::new().create().into_piped();
+            let mut src = text.into_source();
+            let mut events = Vec::new();
+            while events.len() < limit {
+                events.push(parser.next_char(&mut src).unwrap().unwrap());
+            }
+            let snapshot = Snapshot::take(&parser,&src).unwrap();
+
+            let mut parser = entities::Builder::new().create().into_piped();
+            let bytes = snapshot.processed().bytes;
+            let mut src = snapshot.restore(&mut parser,text[bytes ..].into_source());
+            while let Some(local_se) = parser.next_char(&mut src).unwrap() {
+                events.push(local_se);
+            }
+            assert_eq!(events,res);
+        }
+    }
+
+    #[test]
+    fn source_chain() {
+        let text = "<p>a &amp;\r\n\r\nb</p>  <b>Ёж</b>\r\nend";
+        let chain = |src: Shift<StrSource<'static>>| src
+            .into_separator_with(source::Classifier::new().with_crlf())
+            .pipe(tagger::Builder::new().create().into_breaker())
+            .pipe(entities::Builder::new().create().into_piped())
+            .merge_separators_with(source::MergePolicy::new().with_lines_to_paragraph(2));
+        let mut src = chain(text.into_source().with_offset(Processed::default()));
+        let res = drain(&mut src);
+
+        for limit in 1 .. res.len() {
+            let mut src = chain(text.into_source().with_offset(Processed::default()));
+            let mut events = Vec::new();
+            while events.len() < limit {
+                events.push(src.next_char().unwrap().unwrap());
+            }
+            let snapshot = Snapshot::take(&src,&src).unwrap();
+
+            let bytes = snapshot.processed().bytes;
+            let mut src = chain(snapshot.offset(text[bytes ..].into_source()));
+            snapshot.restore_source(&mut src);
+            events.extend(drain(&mut src));
+            assert_eq!(events,res);
+        }
+    }
+
+    #[test]
+    fn case_fold() {
+        let text = "ΟΔΟΣ ΣΟΣ";
+        let chain = |src: Shift<StrSource<'static>>| src.into_separator().case_fold_with(CaseFold::new().final_sigma());
+        let mut src = chain(text.into_source().with_offset(Processed::default()));
+        let res = drain(&mut src);
+        assert_eq!(res.iter().filter(|lse| *lse.data() == SourceEvent::Char('ς')).count(),2);
+
+        for limit in 1 .. res.len() {
+            let mut src = chain(text.into_source().with_offset(Processed::default()));
+            let mut events = Vec::new();
+            while events.len() < limit {
+                events.push(src.next_char().unwrap().unwrap());
+            }
+            let snapshot = Snapshot::take(&src,&src).unwrap();
+
+            let bytes = snapshot.processed().bytes;
+            let mut src = chain(snapshot.offset(text[bytes ..].into_source()));
+            snapshot.restore_source(&mut src);
+            events.extend(drain(&mut src));
+            assert_eq!(events,res);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let text = "<p>Ёж <a href=\"x\">link</a></p> &amp;";
+        let builder = tagger::Builder::new().with_attribute(tagger::TagName::A,"href");
+        let res = run(&mut builder.clone().create(),&mut text.into_source(),None);
+
+        let mut parser = builder.clone().create();
+        let mut src = text.into_source();
+        let mut events = run(&mut parser,&mut src,Some(4));
+        let json = serde_json::to_string(&Snapshot::take(&parser,&src).unwrap()).unwrap();
+
+        let snapshot: Snapshot<tagger::TagCheckpoint> = serde_json::from_str(&json).unwrap();
+        let mut parser = builder.create();
+        let bytes = snapshot.processed().bytes;
+        let mut src = snapshot.restore(&mut parser,text[bytes ..].into_source());
+        events.extend(run(&mut parser,&mut src,None));
+        assert_eq!(events,res);
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{
+    Snip, Localize,
+    Source, SourceResult, Processed,
+    source::Sourcefy,
+    encoding::{Utf8Mode, incomplete_utf8},
+    Error,
+};
+
+/*
+
+   Push-based UTF-8 source: bytes are fed in chunks through the Feeder (a cloneable handle),
+   the source itself can be moved into any pipeline (pipe, into_separator, ...).
+
+   When there is no data yet the source returns Error::Pending (a code point split between
+   chunks is kept until the rest of it arrives), parsers keep their state on Error::Pending,
+   so the pipeline can be polled again after the next chunk. Feeder::close marks the end of input.
+
+      let (src, feeder) = ChunkSource::new();
+      let mut parser = tagger::Builder::new().create();
+      feeder.push(b"<p>Hel");
+      parser.next_event(&mut src) ... Err(Error::Pending)
+      feeder.push(b"lo</p>");
+      feeder.close();
+      parser.next_event(&mut src) ... Ok(Some(..)), ..., Ok(None)
+
+*/
+
+#[derive(Debug,Default)]
+struct Shared {
+    buffer: Vec<u8>,
+    position: usize,
+    closed: bool,
+    error: Option<std::io::Error>,
+}
+
+fn lock(shared: &Mutex<Shared>) -> MutexGuard<'_,Shared> {
+    shared.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug,Clone)]
+pub struct Feeder {
+    shared: Arc<Mutex<Shared>>,
+}
+impl Feeder {
+    pub fn push(&self, bytes: &[u8]) {
+        let mut shared = lock(&self.shared);
+        if shared.position > 0 {
+            let position = shared.position;
+            shared.buffer.drain(.. position);
+            shared.position = 0;
+        }
+        shared.buffer.extend_from_slice(bytes);
+    }
+    pub fn close(&self) {
+        lock(&self.shared).closed = true;
+    }
+    // closes the input with an error, the source returns Error::Io after the data pushed before
+    pub fn fail(&self, error: std::io::Error) {
+        let mut shared = lock(&self.shared);
+        shared.closed = true;
+        shared.error = Some(error);
+    }
+    pub fn is_closed(&self) -> bool {
+        lock(&self.shared).closed
+    }
+}
+
+pub struct ChunkSource {
+    shared: Arc<Mutex<Shared>>,
+    mode: Utf8Mode,
+    done: Processed,
+}
+impl ChunkSource {
+    pub fn new() -> (ChunkSource, Feeder) {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        (ChunkSource {
+            shared: shared.clone(),
+            mode: Utf8Mode::Strict,
+            done: Processed::default(),
+        }, Feeder { shared })
+    }
+    pub fn with_mode(mut self, mode: Utf8Mode) -> ChunkSource {
+        self.mode = mode;
+        self
+    }
+}
+
+impl Source for ChunkSource {
+    fn next_char(&mut self) -> SourceResult {
+        let mut shared = lock(&self.shared);
+        let closed = shared.closed;
+        let avail = &shared.buffer[shared.position ..];
+        if avail.is_empty() || (!closed && incomplete_utf8(avail)) {
+            return match (closed,shared.error.take()) {
+                (true,Some(error)) => Err(Error::Io { local: self.done.position(), error }),
+                (true,None) => Ok(None),
+                (false,_) => Err(Error::Pending),
+            };
+        }
+        let (c,len) = match self.mode.decode(avail,self.done) {
+            Ok(r) => r,
+            Err((len,e)) => {
+                shared.position += len;
+                self.done.bytes += len;
+                return Err(e);
+            },
+        };
+        let chars = Snip { offset: self.done.chars, length: 1 };
+        let bytes = Snip { offset: self.done.bytes, length: len };
+        let utf16 = Snip { offset: self.done.utf16, length: c.len_utf16() };
+        shared.position += len;
+        self.done.chars += 1;
+        self.done.bytes += len;
+        self.done.utf16 += utf16.length;
+        Ok(Some(c.sourcefy().localize_utf16(chars,bytes,utf16)))
+    }
+    fn processed(&self) -> Processed {
+        self.done
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn chunks() {
+        let text = "<p>Привет, &#128175; 💯 мир!</p><br/>";
+
+        let mut res = Vec::new();
+        let mut src = text.into_source();
+        let mut parser = tagger::Builder::auto_detect().create();
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            res.push(local_event);
+        }
+
+        for chunk in [1,2,3,5] {
+            let (mut src, feeder) = ChunkSource::new();
+            let mut parser = tagger::Builder::auto_detect().create();
+            let mut chunks = text.as_bytes().chunks(chunk);
+            let mut events = Vec::new();
+            loop {
+                match parser.next_event(&mut src) {
+                    Ok(Some(local_event)) => events.push(local_event),
+                    Ok(None) => break,
+                    Err(Error::Pending) => match chunks.next() {
+                        Some(bytes) => feeder.push(bytes),
+                        None => feeder.close(),
+                    },
+                    Err(e) => panic!("unexpected error: {:?}",e),
+                }
+            }
+            assert_eq!(events,res);
+            assert_eq!(src.processed(),Processed::of(text));
+        }
+    }
+
+    #[test]
+    fn pending_and_close() {
+        let (src, feeder) = ChunkSource::new();
+        let mut src = src.pipe(entities::Builder::new().create().into_piped());
+        assert!(matches!(src.next_char(),Err(Error::Pending)));
+        feeder.push(b"&amp");
+        assert!(src.next_char().unwrap_err().is_pending());
+        feeder.push(b"; \xF0\x9F");
+        assert_eq!(src.next_char().unwrap(),Some(SourceEvent::Char('&').localize(Snip { offset: 0, length: 5 },Snip { offset: 0, length: 5 })));
+        assert_eq!(src.next_char().unwrap(),Some(SourceEvent::Char(' ').localize(Snip { offset: 5, length: 1 },Snip { offset: 5, length: 1 })));
+        assert!(matches!(src.next_char(),Err(Error::Pending)));
+        feeder.close();
+        match src.next_char() {
+            Err(Error::InvalidUtf8 { local, .. }) => assert_eq!(local.bytes(),Snip { offset: 6, length: 2 }),
+            r => panic!("unexpected result: {:?}",r),
+        }
+        assert_eq!(src.next_char().unwrap(),None);
+    }
+}
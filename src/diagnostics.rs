@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{
+    Local,
+    tagger::TagName,
+};
+
+/*
+
+   Non-fatal diagnostics: problems parsers recover from silently (the output is the same
+   with or without diagnostics), recorded into an opt-in sink with the Local of the problem.
+
+      let diagnostics = Diagnostics::new();
+      let parser = entities::Builder::new().with_diagnostics(diagnostics.clone()).create();
+      ...
+      for local_warning in diagnostics.take() { ... }
+
+   The sink is shared (cloned handles write into the same list), so one sink can be given
+   to several builders of a pipeline.
+
+*/
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub enum Warning {
+    // entities
+    UnknownEntity(String),      // "&foo;", "AT&T" (no such entity, kept as text)
+    InvalidCodePoint(String),   // "&#1114112;", "&#xD800;", "&#xZZ;" (kept as text)
+    UnterminatedEntity(String), // "&#128175 " (numeric entity without ';', kept as text)
+
+    // tags
+    StrayLessThan,              // '<' not starting a tag (kept as text)
+    UnclosedAttributeQuote,     // end of input inside a quoted attribute value
+    MismatchedCloseTag(TagName),// close tag without the matching open tag on top (optional end tags aside)
+
+    // paragraphs
+    ControlChar(char),          // control char except TAB, LF, FF, CR
+}
+
+#[derive(Debug,Clone,Default)]
+pub struct Diagnostics(Arc<Mutex<Vec<Local<Warning>>>>);
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_,Vec<Local<Warning>>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn push(&self, local: Local<()>, warning: Warning) {
+        self.lock().push(local.local(warning));
+    }
+    // takes all the warnings recorded so far
+    pub fn take(&self) -> Vec<Local<Warning>> {
+        std::mem::take(&mut *self.lock())
+    }
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+}
+
+// warning is created only if there is a sink
+pub(crate) fn warn<F: FnOnce() -> Warning>(diagnostics: &Option<Diagnostics>, local: Local<()>, warning: F) {
+    if let Some(diagnostics) = diagnostics {
+        diagnostics.push(local,warning());
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn run<P: Parser>(mut parser: P, text: &str) -> Vec<String> where P::Data: std::fmt::Debug {
+        let mut src = text.into_source();
+        let mut res = Vec::new();
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            res.push(format!("{:?}",local_event));
+        }
+        res
+    }
+
+    fn warnings(diagnostics: &Diagnostics) -> Vec<(usize,usize,Warning)> {
+        diagnostics.take().into_iter().map(|lw| (lw.chars().offset,lw.chars().length,lw.data().clone())).collect()
+    }
+
+    #[test]
+    fn entities() {
+        let text = "AT&T &foo; &#1114112; &#x41; &#128175 &amp;";
+        let diagnostics = Diagnostics::new();
+        let res = run(entities::Builder::new().with_diagnostics(diagnostics.clone()).create(),text);
+        assert_eq!(res,run(entities::Builder::new().create(),text));
+        assert_eq!(warnings(&diagnostics),vec![
+            (2,2,Warning::UnknownEntity("&T".to_string())),
+            (5,5,Warning::UnknownEntity("&foo;".to_string())),
+            (11,10,Warning::InvalidCodePoint("&#1114112;".to_string())),
+            (29,8,Warning::UnterminatedEntity("&#128175".to_string())),
+        ]);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn tagger() {
+        let text = "<div>a < b <p>c<b>d</i></b> <a href=\"x>";
+        let builder = tagger::Builder::new().text_eof_in_tag().eof_to_named_tag();
+        let diagnostics = Diagnostics::new();
+        let res = run(builder.clone().with_diagnostics(diagnostics.clone()).create(),text);
+        assert_eq!(res,run(builder.create(),text));
+        assert_eq!(warnings(&diagnostics),vec![
+            (7,1,Warning::StrayLessThan),
+            (19,4,Warning::MismatchedCloseTag(tagger::TagName::I)),
+            (28,11,Warning::UnclosedAttributeQuote),
+        ]);
+    }
+
+    #[test]
+    fn optional_end() {
+        let text = "<ul><li>a<li><p>b</ul><div><b>c</div>";
+        let diagnostics = Diagnostics::new();
+        run(tagger::Builder::new().with_diagnostics(diagnostics.clone()).create(),text);
+        assert_eq!(warnings(&diagnostics),vec![
+            (31,6,Warning::MismatchedCloseTag(tagger::TagName::Div)),
+        ]);
+    }
+
+    #[test]
+    fn paragraphs() {
+        let text = "a\u{7}b\tc\n\nd\u{0}";
+        let diagnostics = Diagnostics::new();
+        let res = run(paragraph::Builder::new().with_diagnostics(diagnostics.clone()).create(),text);
+        assert_eq!(res,run(paragraph::Builder::new().create(),text));
+        assert_eq!(warnings(&diagnostics),vec![
+            (1,1,Warning::ControlChar('\u{7}')),
+            (8,1,Warning::ControlChar('\u{0}')),
+        ]);
+    }
+}
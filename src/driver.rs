@@ -0,0 +1,262 @@
+use std::{
+    pin::Pin,
+    future::poll_fn,
+    io::ErrorKind,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use futures_io::AsyncRead;
+
+use crate::{
+    Feeder,
+    Error,
+};
+
+const CHUNK: usize = 8192;
+
+/*
+
+   Async driving of parsers (feature "async"), runtime-agnostic (futures-io / futures-core traits,
+   tokio readers can be used via tokio-util compat).
+
+   AsyncDriver feeds a ChunkSource from an AsyncRead or a Stream of byte chunks,
+   any synchronous step of the pipeline (parser.next_event, src.next_char, ...) is retried
+   after the next chunk while it returns Error::Pending, so the thread is never blocked.
+   Input errors are passed to the source (Feeder::fail) and returned by it as Error::Io
+   after the data read before the error:
+
+      let (src, feeder) = ChunkSource::new();
+      let mut src = src.pipe(entities::Builder::new().create().into_piped());
+      let mut driver = AsyncDriver::from_reader(reader, feeder);
+      while let Some(local_se) = driver.next(|| src.next_char()).await? { ... }
+
+*/
+
+pub trait AsyncInput {
+    // pushes the next chunk to the feeder (true) or closes it at the end of input or on error (false)
+    fn poll_feed(&mut self, cx: &mut Context<'_>, feeder: &Feeder) -> Poll<bool>;
+}
+
+pub struct ReadInput<R> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+impl<R: AsyncRead + Unpin> AsyncInput for ReadInput<R> {
+    fn poll_feed(&mut self, cx: &mut Context<'_>, feeder: &Feeder) -> Poll<bool> {
+        loop {
+            match Pin::new(&mut self.reader).poll_read(cx,&mut self.buffer) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(0)) => {
+                    feeder.close();
+                    return Poll::Ready(false);
+                },
+                Poll::Ready(Ok(n)) => {
+                    feeder.push(&self.buffer[.. n]);
+                    return Poll::Ready(true);
+                },
+                Poll::Ready(Err(e)) if e.kind() == ErrorKind::Interrupted => continue,
+                Poll::Ready(Err(e)) => {
+                    feeder.fail(e);
+                    return Poll::Ready(false);
+                },
+            }
+        }
+    }
+}
+
+pub struct StreamInput<St> {
+    stream: St,
+}
+impl<St,B> AsyncInput for StreamInput<St>
+where St: Stream<Item = Result<B,std::io::Error>> + Unpin,
+      B: AsRef<[u8]>
+{
+    fn poll_feed(&mut self, cx: &mut Context<'_>, feeder: &Feeder) -> Poll<bool> {
+        Poll::Ready(match Pin::new(&mut self.stream).poll_next(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(None) => {
+                feeder.close();
+                false
+            },
+            Poll::Ready(Some(Ok(bytes))) => {
+                feeder.push(bytes.as_ref());
+                true
+            },
+            Poll::Ready(Some(Err(e))) => {
+                feeder.fail(e);
+                false
+            },
+        })
+    }
+}
+
+pub struct AsyncDriver<I> {
+    input: I,
+    feeder: Feeder,
+    eof: bool,
+}
+impl<R: AsyncRead + Unpin> AsyncDriver<ReadInput<R>> {
+    pub fn from_reader(reader: R, feeder: Feeder) -> AsyncDriver<ReadInput<R>> {
+        AsyncDriver::new(ReadInput { reader, buffer: vec![0; CHUNK] },feeder)
+    }
+}
+impl<St,B> AsyncDriver<StreamInput<St>>
+where St: Stream<Item = Result<B,std::io::Error>> + Unpin,
+      B: AsRef<[u8]>
+{
+    pub fn from_stream(stream: St, feeder: Feeder) -> AsyncDriver<StreamInput<St>> {
+        AsyncDriver::new(StreamInput { stream },feeder)
+    }
+}
+impl<I: AsyncInput> AsyncDriver<I> {
+    pub fn new(input: I, feeder: Feeder) -> AsyncDriver<I> {
+        AsyncDriver {
+            input,
+            feeder,
+            eof: false,
+        }
+    }
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    // feeds the next chunk, false if the input is over
+    pub async fn feed(&mut self) -> bool {
+        if self.eof {
+            return false;
+        }
+        let more = poll_fn(|cx| self.input.poll_feed(cx,&self.feeder)).await;
+        self.eof = !more;
+        more
+    }
+
+    // runs the step, feeding the input while the step is pending
+    pub async fn next<T,F>(&mut self, mut step: F) -> Result<Option<T>,Error>
+    where F: FnMut() -> Result<Option<T>,Error>
+    {
+        loop {
+            match step() {
+                Err(Error::Pending) if !self.eof => { self.feed().await; },
+                r => break r,
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use std::{
+        future::Future,
+        task::Waker,
+    };
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = std::pin::pin!(fut);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(r) = fut.as_mut().poll(&mut cx) {
+                break r;
+            }
+        }
+    }
+
+    // yields Pending before every chunk
+    struct Chunks<'s> {
+        chunks: std::slice::Chunks<'s,u8>,
+        ready: bool,
+    }
+    impl<'s> Stream for Chunks<'s> {
+        type Item = Result<&'s [u8],std::io::Error>;
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            self.ready = !self.ready;
+            match self.ready {
+                true => Poll::Ready(self.chunks.next().map(Ok)),
+                false => {
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn reader() {
+        let text = "<p>Привет, &#128175; 💯 &amp;&nbsp;мир!</p>";
+
+        let mut res = Vec::new();
+        let mut src = text.into_source().pipe(entities::Builder::new().create().into_piped());
+        while let Some(local_se) = src.next_char().unwrap() {
+            res.push(local_se);
+        }
+
+        let (src, feeder) = ChunkSource::new();
+        let mut src = src.pipe(entities::Builder::new().create().into_piped());
+        let mut driver = AsyncDriver::from_reader(text.as_bytes(),feeder);
+        let events = block_on(async {
+            let mut events = Vec::new();
+            while let Some(local_se) = driver.next(|| src.next_char()).await.unwrap() {
+                events.push(local_se);
+            }
+            events
+        });
+        assert_eq!(events,res);
+    }
+
+    #[test]
+    fn stream() {
+        let text = "<p>Привет, &#128175; 💯 мир!</p><br/>";
+
+        let mut res = Vec::new();
+        let mut src = text.into_source();
+        let mut parser = tagger::Builder::auto_detect().create();
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            res.push(local_event);
+        }
+
+        for chunk in [1,3,7] {
+            let (mut src, feeder) = ChunkSource::new();
+            let mut parser = tagger::Builder::auto_detect().create();
+            let stream = Chunks { chunks: text.as_bytes().chunks(chunk), ready: false };
+            let mut driver = AsyncDriver::from_stream(stream,feeder);
+            let events = block_on(async {
+                let mut events = Vec::new();
+                while let Some(local_event) = driver.next(|| parser.next_event(&mut src)).await.unwrap() {
+                    events.push(local_event);
+                }
+                events
+            });
+            assert_eq!(events,res);
+        }
+    }
+
+    #[test]
+    fn io_error() {
+        struct Broken(bool);
+        impl Stream for Broken {
+            type Item = Result<&'static [u8],std::io::Error>;
+            fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                self.0 = !self.0;
+                Poll::Ready(Some(match self.0 {
+                    true => Ok("Ёж".as_bytes()),
+                    false => Err(std::io::Error::other("connection reset")),
+                }))
+            }
+        }
+
+        let (mut src, feeder) = ChunkSource::new();
+        let mut driver = AsyncDriver::from_stream(Broken(false),feeder);
+        block_on(async {
+            assert!(driver.next(|| src.next_char()).await.unwrap().is_some());
+            assert!(driver.next(|| src.next_char()).await.unwrap().is_some());
+            match driver.next(|| src.next_char()).await {
+                Err(Error::Io { local, .. }) => assert_eq!(local.bytes(),Snip { offset: 4, length: 0 }),
+                r => panic!("unexpected result: {:?}",r),
+            }
+            assert_eq!(driver.next(|| src.next_char()).await.unwrap(),None);
+        });
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    Snip, Localize,
+    Source, SourceResult, Processed,
+    source::Sourcefy,
+};
+
+use super::tables::{
+    WINDOWS_1251, WINDOWS_1252, KOI8_R,
+};
+use super::utf8::{
+    REPLACEMENT, next_utf8,
+};
+
+/*
+
+   Decoding sources: chars are decoded from the original encoded buffer,
+
+      Local::chars() is an offset in decoded chars,
+      Local::bytes() is an offset in the original (encoded) bytes, not in utf-8.
+
+   Malformed input (unpaired surrogates, truncated sequences, invalid utf-8) is decoded as U+FFFD,
+   its byte span covers exactly the malformed bytes. BOM is not stripped, it is decoded as U+FEFF
+   (the same way StrSource does).
+
+*/
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Windows1251,
+    Windows1252,
+    Koi8R,
+    Latin1,
+}
+impl Encoding {
+    pub fn decode(self, bytes: &[u8]) -> DecodingSource<'_> {
+        DecodingSource::new(bytes,self)
+    }
+
+    // decodes the first char of non-empty bytes, returns char and its length in bytes
+    fn next_char(self, bytes: &[u8]) -> (char,usize) {
+        match self {
+            Encoding::Utf8 => next_utf8(bytes).unwrap_or_else(|len| (REPLACEMENT,len)),
+            Encoding::Utf16Le => utf16(bytes,u16::from_le_bytes),
+            Encoding::Utf16Be => utf16(bytes,u16::from_be_bytes),
+            Encoding::Windows1251 => (single_byte(bytes[0],&WINDOWS_1251),1),
+            Encoding::Koi8R => (single_byte(bytes[0],&KOI8_R),1),
+            Encoding::Windows1252 => (match bytes[0] {
+                b @ 0x80 ..= 0x9F => WINDOWS_1252[(b - 0x80) as usize],
+                b => b as char,
+            },1),
+            Encoding::Latin1 => (bytes[0] as char,1),
+        }
+    }
+}
+
+fn single_byte(b: u8, table: &[char; 128]) -> char {
+    match b {
+        0x00 ..= 0x7F => b as char,
+        _ => table[(b - 0x80) as usize],
+    }
+}
+
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> (char,usize) {
+    if bytes.len() < 2 {
+        return (REPLACEMENT,bytes.len());
+    }
+    match unit([bytes[0],bytes[1]]) {
+        hi @ 0xD800 ..= 0xDBFF => {
+            if bytes.len() >= 4 {
+                let lo = unit([bytes[2],bytes[3]]);
+                if (0xDC00 ..= 0xDFFF).contains(&lo) {
+                    let u = 0x10000 + (((hi as u32) - 0xD800) << 10) + ((lo as u32) - 0xDC00);
+                    if let Some(c) = char::from_u32(u) {
+                        return (c,4);
+                    }
+                }
+            }
+            (REPLACEMENT,2)
+        },
+        u => (char::from_u32(u as u32).unwrap_or(REPLACEMENT),2),
+    }
+}
+
+
+pub struct DecodingSource<'s> {
+    bytes: &'s [u8],
+    encoding: Encoding,
+    done: Processed,
+}
+impl<'s> DecodingSource<'s> {
+    pub fn new(bytes: &'s [u8], encoding: Encoding) -> DecodingSource<'s> {
+        DecodingSource {
+            bytes,
+            encoding,
+            done: Processed::default(),
+        }
+    }
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+}
+impl<'s> Source for DecodingSource<'s> {
+    fn next_char(&mut self) -> SourceResult {
+        let rest = &self.bytes[self.done.bytes ..];
+        if rest.is_empty() {
+            return Ok(None);
+        }
+        let (c,len) = self.encoding.next_char(rest);
+        let chars = Snip { offset: self.done.chars, length: 1 };
+        let bytes = Snip { offset: self.done.bytes, length: len };
+        let utf16 = Snip { offset: self.done.utf16, length: c.len_utf16() };
+        self.done.chars += 1;
+        self.done.bytes += len;
+        self.done.utf16 += utf16.length;
+        Ok(Some(c.sourcefy().localize_utf16(chars,bytes,utf16)))
+    }
+    fn processed(&self) -> Processed {
+        self.done
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use super::*;
+
+    fn check<S: Source>(mut src: S, result: Vec<Local<SourceEvent>>) {
+        let mut res_iter = result.into_iter();
+        while let Some(local_event) = src.next_char().unwrap() {
+            //let (local,event) = local_event.into_inner();
+            //println!("SourceEvent::{:?}.localize({:?},{:?}),",event,local.chars(),local.bytes());
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Source: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+    }
+
+    #[test]
+    fn windows_1251() {
+        // "&lt;Мир &amp; Ёж" in cp1251
+        let bytes = b"&lt;\xCC\xE8\xF0 &amp; \xA8\xE6";
+        let src = Encoding::Windows1251.decode(bytes)
+            .pipe(crate::entities::Builder::new().create().into_piped())
+            .into_separator();
+
+        check(src,vec![
+            SourceEvent::Char('<').localize(Snip { offset: 0, length: 4 },Snip { offset: 0, length: 4 }),
+            SourceEvent::Char('М').localize(Snip { offset: 4, length: 1 },Snip { offset: 4, length: 1 }),
+            SourceEvent::Char('и').localize(Snip { offset: 5, length: 1 },Snip { offset: 5, length: 1 }),
+            SourceEvent::Char('р').localize(Snip { offset: 6, length: 1 },Snip { offset: 6, length: 1 }),
+            SourceEvent::Breaker(Breaker::Space).localize(Snip { offset: 7, length: 1 },Snip { offset: 7, length: 1 }),
+            SourceEvent::Char('&').localize(Snip { offset: 8, length: 5 },Snip { offset: 8, length: 5 }),
+            SourceEvent::Breaker(Breaker::Space).localize(Snip { offset: 13, length: 1 },Snip { offset: 13, length: 1 }),
+            SourceEvent::Char('Ё').localize(Snip { offset: 14, length: 1 },Snip { offset: 14, length: 1 }),
+            SourceEvent::Char('ж').localize(Snip { offset: 15, length: 1 },Snip { offset: 15, length: 1 }),
+        ]);
+    }
+
+    #[test]
+    fn koi8_r() {
+        // "Ёж!" in koi8-r
+        let src = Encoding::Koi8R.decode(b"\xB3\xD6!");
+        check(src,vec![
+            SourceEvent::Char('Ё').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            SourceEvent::Char('ж').localize(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 1 }),
+            SourceEvent::Char('!').localize(Snip { offset: 2, length: 1 },Snip { offset: 2, length: 1 }),
+        ]);
+    }
+
+    #[test]
+    fn latin1() {
+        let src = Encoding::Latin1.decode(b"caf\xE9\x80");
+        check(src.filter_char(|c| match c.is_control() {
+            true => None,
+            false => Some(c),
+        }),vec![
+            SourceEvent::Char('c').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            SourceEvent::Char('a').localize(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 1 }),
+            SourceEvent::Char('f').localize(Snip { offset: 2, length: 1 },Snip { offset: 2, length: 1 }),
+            SourceEvent::Char('é').localize(Snip { offset: 3, length: 1 },Snip { offset: 3, length: 1 }),
+        ]);
+
+        let src = Encoding::Windows1252.decode(b"\x80\x81\xE9");
+        check(src,vec![
+            SourceEvent::Char('€').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            SourceEvent::Char('\u{81}').localize(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 1 }),
+            SourceEvent::Char('é').localize(Snip { offset: 2, length: 1 },Snip { offset: 2, length: 1 }),
+        ]);
+    }
+
+    #[test]
+    fn utf16() {
+        // BOM, "Я", U+1F4AF, unpaired high surrogate, "!", odd trailing byte
+        let le = b"\xFF\xFE\x2F\x04\x3D\xD8\xAF\xDC\x3D\xD8\x21\x00\x21";
+        let be = b"\xFE\xFF\x04\x2F\xD8\x3D\xDC\xAF\xD8\x3D\x00\x21\x00";
+        let result = vec![
+            SourceEvent::Char('\u{FEFF}').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 2 }),
+            SourceEvent::Char('Я').localize(Snip { offset: 1, length: 1 },Snip { offset: 2, length: 2 }),
+            SourceEvent::Char('💯').localize_utf16(Snip { offset: 2, length: 1 },Snip { offset: 4, length: 4 },Snip { offset: 2, length: 2 }),
+            SourceEvent::Char('\u{FFFD}').localize_utf16(Snip { offset: 3, length: 1 },Snip { offset: 8, length: 2 },Snip { offset: 4, length: 1 }),
+            SourceEvent::Char('!').localize_utf16(Snip { offset: 4, length: 1 },Snip { offset: 10, length: 2 },Snip { offset: 5, length: 1 }),
+            SourceEvent::Char('\u{FFFD}').localize_utf16(Snip { offset: 5, length: 1 },Snip { offset: 12, length: 1 },Snip { offset: 6, length: 1 }),
+        ];
+        check(Encoding::Utf16Le.decode(le),result.clone());
+        check(Encoding::Utf16Be.decode(be),result);
+
+        let mut src = Encoding::Utf16Le.decode(le);
+        while src.next_char().unwrap().is_some() {}
+        assert_eq!(src.processed(),Processed { chars: 6, bytes: 13, utf16: 7 });
+    }
+
+    #[test]
+    fn utf8_lossy() {
+        let src = Encoding::Utf8.decode(b"a\xF0\x9F\x92b\xFF\xD1\x8F");
+        check(src,vec![
+            SourceEvent::Char('a').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 3 }),
+            SourceEvent::Char('b').localize(Snip { offset: 2, length: 1 },Snip { offset: 4, length: 1 }),
+            SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 3, length: 1 },Snip { offset: 5, length: 1 }),
+            SourceEvent::Char('я').localize(Snip { offset: 4, length: 1 },Snip { offset: 6, length: 2 }),
+        ]);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{
+    Snip, Local,
+    SourceEvent, ParserEvent,
+    Parser,
+    tagger::{
+        self,
+        Tag, TagName,
+    },
+};
+
+use super::decoder::{
+    Encoding, DecodingSource,
+};
+
+/*
+
+  Encoding sniffing: https://html.spec.whatwg.org/multipage/parsing.html#determining-the-character-encoding
+
+     1) BOM
+     2) prescan of the first bytes: <meta charset="..."> or <meta http-equiv="Content-Type" content="...; charset=...">
+     3) default
+
+  Prescan is done by the tagger over the prefix decoded as windows-1252 (all the markup is ascii,
+  so any ascii-compatible decoding will do).
+
+*/
+
+const PRESCAN: usize = 1024;
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+pub enum Detected {
+    Bom(Encoding),
+    Meta(Encoding),
+    Default(Encoding),
+}
+impl Detected {
+    pub fn encoding(&self) -> Encoding {
+        match self {
+            Detected::Bom(e) |
+            Detected::Meta(e) |
+            Detected::Default(e) => *e,
+        }
+    }
+}
+
+impl Encoding {
+    pub fn from_bom(bytes: &[u8]) -> Option<Encoding> {
+        match bytes {
+            [0xEF, 0xBB, 0xBF, ..] => Some(Encoding::Utf8),
+            [0xFF, 0xFE, ..] => Some(Encoding::Utf16Le),
+            [0xFE, 0xFF, ..] => Some(Encoding::Utf16Be),
+            _ => None,
+        }
+    }
+
+    // https://encoding.spec.whatwg.org/#names-and-labels (only supported encodings)
+    pub fn for_label(label: &str) -> Option<Encoding> {
+        match &label.trim_matches(|c: char| c.is_ascii_whitespace()).to_ascii_lowercase() as &str {
+            "unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" |
+            "utf-8" | "utf8" | "x-unicode20utf8" => Some(Encoding::Utf8),
+
+            "cp1251" | "windows-1251" | "x-cp1251" => Some(Encoding::Windows1251),
+
+            "ansi_x3.4-1968" | "ascii" | "cp1252" | "cp819" | "csisolatin1" | "ibm819" |
+            "iso-8859-1" | "iso-ir-100" | "iso8859-1" | "iso88591" | "iso_8859-1" |
+            "iso_8859-1:1987" | "l1" | "latin1" | "us-ascii" | "windows-1252" | "x-cp1252" => Some(Encoding::Windows1252),
+
+            "cskoi8r" | "koi" | "koi8" | "koi8-r" | "koi8_r" => Some(Encoding::Koi8R),
+
+            "csunicode" | "iso-10646-ucs-2" | "ucs-2" | "unicode" | "unicodefeff" |
+            "utf-16" | "utf-16le" => Some(Encoding::Utf16Le),
+
+            "unicodefffe" | "utf-16be" => Some(Encoding::Utf16Be),
+
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct Sniffer {
+    default: Encoding,
+    prescan: usize,
+}
+impl Default for Sniffer {
+    fn default() -> Sniffer {
+        Sniffer::new()
+    }
+}
+impl Sniffer {
+    pub fn new() -> Sniffer {
+        Sniffer {
+            default: Encoding::Windows1252,
+            prescan: PRESCAN,
+        }
+    }
+    pub fn with_default(mut self, encoding: Encoding) -> Sniffer {
+        self.default = encoding;
+        self
+    }
+    pub fn with_prescan(mut self, bytes: usize) -> Sniffer {
+        self.prescan = bytes;
+        self
+    }
+
+    pub fn detect(&self, bytes: &[u8]) -> Detected {
+        if let Some(e) = Encoding::from_bom(bytes) {
+            return Detected::Bom(e);
+        }
+        match self.prescan(&bytes[.. bytes.len().min(self.prescan)]) {
+            Some(e) => Detected::Meta(e),
+            None => Detected::Default(self.default),
+        }
+    }
+
+    pub fn sniff<'s>(&self, bytes: &'s [u8]) -> (Detected,DecodingSource<'s>) {
+        let detected = self.detect(bytes);
+        (detected,detected.encoding().decode(bytes))
+    }
+
+    fn prescan(&self, bytes: &[u8]) -> Option<Encoding> {
+        let mut src = Encoding::Windows1252.decode(bytes);
+        let mut parser = tagger::Builder::new()
+            .with_attribute(TagName::Meta,"charset")
+            .with_attribute(TagName::Meta,"http-equiv")
+            .with_attribute(TagName::Meta,"content")
+            .skip_eof_in_tag()
+            .create();
+
+        while let Ok(Some(local_pe)) = parser.next_event(&mut src) {
+            if let ParserEvent::Parsed(tag) = local_pe.data() {
+                if tag.name != TagName::Meta { continue; }
+                if let Some(e) = meta_encoding(tag) {
+                    // https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding
+                    return Some(match e {
+                        Encoding::Utf16Le | Encoding::Utf16Be => Encoding::Utf8,
+                        e => e,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+fn attribute(tag: &Tag, name: &str) -> Option<String> {
+    tag.attributes.iter()
+        .find(|(aname,_)| aname == name)
+        .map(|(_,snip)| match snip {
+            Some(snip) => raw_value(&tag.raw,*snip),
+            None => String::new(),
+        })
+}
+
+fn raw_value(raw: &[Local<SourceEvent>], snip: Snip) -> String {
+    raw.iter()
+        .skip(snip.offset)
+        .take(snip.length)
+        .filter_map(|lse| match lse.data() {
+            SourceEvent::Char(c) => Some(*c),
+            SourceEvent::Breaker(_) => None,
+        })
+        .collect()
+}
+
+fn meta_encoding(tag: &Tag) -> Option<Encoding> {
+    if let Some(label) = attribute(tag,"charset") {
+        return Encoding::for_label(&label);
+    }
+    match attribute(tag,"http-equiv") {
+        Some(he) if he.eq_ignore_ascii_case("content-type") => {
+            attribute(tag,"content").and_then(|content| charset_from_content(&content))
+        },
+        _ => None,
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#algorithm-for-extracting-a-character-encoding-from-a-meta-element
+fn charset_from_content(content: &str) -> Option<Encoding> {
+    let lower = content.to_ascii_lowercase();
+    let mut pos = 0;
+    loop {
+        let found = lower[pos ..].find("charset")?;
+        pos += found + "charset".len();
+        let rest = lower[pos ..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+        let rest = match rest.strip_prefix('=') {
+            Some(rest) => rest.trim_start_matches(|c: char| c.is_ascii_whitespace()),
+            None => continue,
+        };
+        let value = match rest.chars().next() {
+            Some(q @ '"') | Some(q @ '\'') => {
+                let rest = &rest[1 ..];
+                &rest[.. rest.find(q)?]
+            },
+            _ => {
+                let end = rest.find(|c: char| c == ';' || c.is_ascii_whitespace()).unwrap_or(rest.len());
+                &rest[.. end]
+            },
+        };
+        return Encoding::for_label(value);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::encoding::*;
+
+    #[test]
+    fn meta_charset() {
+        // "Мир" in cp1251
+        let html = b"<html><head><meta charset=\"Windows-1251\"><title>x</title></head><body>\xCC\xE8\xF0</body>";
+        let (detected,src) = Sniffer::new().sniff(html);
+        assert_eq!(detected,Detected::Meta(Encoding::Windows1251));
+
+        let mut src = src.pipe(tagger::Builder::new().create().into_breaker());
+        let mut text = String::new();
+        while let Some(local_se) = src.next_char().unwrap() {
+            if let SourceEvent::Char(c) = local_se.data() {
+                text.push(*c);
+            }
+        }
+        assert_eq!(text,"xМир");
+    }
+
+    #[test]
+    fn http_equiv() {
+        let html = b"<!DOCTYPE html>\n<meta name=viewport content=\"width=device-width\">\n<META HTTP-EQUIV='Content-Type' CONTENT='text/html; charset=koi8-r'>\xB3\xD6";
+        assert_eq!(Sniffer::new().detect(html),Detected::Meta(Encoding::Koi8R));
+
+        let html = b"<meta http-equiv=content-type content=\"text/html;charset = 'cp1251'\">";
+        assert_eq!(Sniffer::new().detect(html),Detected::Meta(Encoding::Windows1251));
+    }
+
+    #[test]
+    fn bom_and_default() {
+        let html = b"\xFF\xFE<\x00p\x00>\x00";
+        assert_eq!(Sniffer::new().detect(html),Detected::Bom(Encoding::Utf16Le));
+
+        let html = b"\xEF\xBB\xBF<meta charset=koi8-r>";
+        assert_eq!(Sniffer::new().detect(html),Detected::Bom(Encoding::Utf8));
+
+        let html = b"<meta charset=utf-16>";
+        assert_eq!(Sniffer::new().detect(html),Detected::Meta(Encoding::Utf8));
+
+        let html = b"<meta charset=bogus><p>text</p>";
+        assert_eq!(Sniffer::new().detect(html),Detected::Default(Encoding::Windows1252));
+
+        let html = b"<p>long prefix</p><meta charset=koi8-r>";
+        assert_eq!(Sniffer::new().with_prescan(10).with_default(Encoding::Utf8).detect(html),Detected::Default(Encoding::Utf8));
+    }
+}
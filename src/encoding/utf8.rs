@@ -0,0 +1,182 @@
+use crate::{
+    Snip, Localize,
+    Source, SourceResult, Processed,
+    source::Sourcefy,
+    Error,
+};
+
+pub(crate) const REPLACEMENT: char = '\u{FFFD}';
+
+/*
+
+   Invalid utf-8 handling:
+
+      Lossy  => U+FFFD, its byte span covers exactly the invalid sequence
+                (maximal subpart, https://www.unicode.org/versions/Unicode15.0.0/ch03.pdf#G66453)
+      Strict => Error::InvalidUtf8 with the byte span of the invalid sequence,
+                the sequence is skipped, so the source can be polled further
+
+*/
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+pub enum Utf8Mode {
+    Lossy,
+    Strict,
+}
+impl Utf8Mode {
+    // decodes the first char of non-empty bytes, done is used for errors only
+    pub(crate) fn decode(self, bytes: &[u8], done: Processed) -> Result<(char,usize),(usize,Error)> {
+        match next_utf8(bytes) {
+            Ok(r) => Ok(r),
+            Err(len) => match self {
+                Utf8Mode::Lossy => Ok((REPLACEMENT,len)),
+                Utf8Mode::Strict => Err((len,Error::InvalidUtf8 {
+                    local: ().localize_utf16(
+                        Snip { offset: done.chars, length: 0 },
+                        Snip { offset: done.bytes, length: len },
+                        Snip { offset: done.utf16, length: 0 },
+                    ),
+                    error: std::str::from_utf8(&bytes[.. len]).expect_err("next_utf8 failed on these bytes"),
+                })),
+            },
+        }
+    }
+}
+
+// decodes the first char of non-empty bytes: Ok(char,length) or Err(length of invalid sequence)
+pub(crate) fn next_utf8(bytes: &[u8]) -> Result<(char,usize),usize> {
+    let head = &bytes[.. bytes.len().min(4)];
+    let valid = match std::str::from_utf8(head) {
+        Ok(s) => s,
+        Err(e) => match e.valid_up_to() {
+            0 => return Err(e.error_len().unwrap_or(head.len())),
+            n => match std::str::from_utf8(&head[.. n]) {
+                Ok(s) => s,
+                Err(_) => return Err(n),
+            },
+        },
+    };
+    match valid.chars().next() {
+        Some(c) => Ok((c,c.len_utf8())),
+        None => Err(head.len()),
+    }
+}
+
+// true if bytes are the beginning of a valid multi-byte char, cut before its end
+pub(crate) fn incomplete_utf8(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(&bytes[.. bytes.len().min(4)]) {
+        Ok(_) => false,
+        Err(e) => (e.valid_up_to() == 0) && e.error_len().is_none(),
+    }
+}
+
+pub struct BytesSource<'s> {
+    bytes: &'s [u8],
+    mode: Utf8Mode,
+    done: Processed,
+}
+impl<'s> BytesSource<'s> {
+    pub fn new(bytes: &'s [u8], mode: Utf8Mode) -> BytesSource<'s> {
+        BytesSource {
+            bytes,
+            mode,
+            done: Processed::default(),
+        }
+    }
+    pub fn lossy(bytes: &'s [u8]) -> BytesSource<'s> {
+        BytesSource::new(bytes,Utf8Mode::Lossy)
+    }
+    pub fn strict(bytes: &'s [u8]) -> BytesSource<'s> {
+        BytesSource::new(bytes,Utf8Mode::Strict)
+    }
+}
+impl<'s> Source for BytesSource<'s> {
+    fn next_char(&mut self) -> SourceResult {
+        let rest = &self.bytes[self.done.bytes ..];
+        if rest.is_empty() {
+            return Ok(None);
+        }
+        let (c,len) = match self.mode.decode(rest,self.done) {
+            Ok(r) => r,
+            Err((len,e)) => {
+                self.done.bytes += len;
+                return Err(e);
+            },
+        };
+        let chars = Snip { offset: self.done.chars, length: 1 };
+        let bytes = Snip { offset: self.done.bytes, length: len };
+        let utf16 = Snip { offset: self.done.utf16, length: c.len_utf16() };
+        self.done.chars += 1;
+        self.done.bytes += len;
+        self.done.utf16 += utf16.length;
+        Ok(Some(c.sourcefy().localize_utf16(chars,bytes,utf16)))
+    }
+    fn processed(&self) -> Processed {
+        self.done
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use super::*;
+
+    #[test]
+    fn lossy() {
+        // overlong, surrogate, truncated 4-byte, lone continuation
+        let bytes = b"a\xC0\xAFb\xED\xA0\x80c\xF0\x9F\x92&amp;\x80";
+        let mut src = BytesSource::lossy(bytes)
+            .pipe(crate::entities::Builder::new().create().into_piped());
+
+        let mut res_iter = [
+            SourceEvent::Char('a').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 1 }),
+            SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 2, length: 1 },Snip { offset: 2, length: 1 }),
+            SourceEvent::Char('b').localize(Snip { offset: 3, length: 1 },Snip { offset: 3, length: 1 }),
+            SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 4, length: 1 },Snip { offset: 4, length: 1 }),
+            SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 5, length: 1 },Snip { offset: 5, length: 1 }),
+            SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 6, length: 1 },Snip { offset: 6, length: 1 }),
+            SourceEvent::Char('c').localize(Snip { offset: 7, length: 1 },Snip { offset: 7, length: 1 }),
+            SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 8, length: 1 },Snip { offset: 8, length: 3 }),
+            SourceEvent::Char('&').localize(Snip { offset: 9, length: 5 },Snip { offset: 11, length: 5 }),
+            SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 14, length: 1 },Snip { offset: 16, length: 1 }),
+        ].into_iter();
+
+        while let Some(local_event) = src.next_char().unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Source: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+        assert_eq!(src.processed(),Processed { chars: 15, bytes: 17, utf16: 15 });
+    }
+
+    #[test]
+    fn strict() {
+        let mut src = BytesSource::strict("мир\u{1F4AF}".as_bytes());
+        let mut res = "мир\u{1F4AF}".into_source();
+        while let Some(local_event) = src.next_char().unwrap() {
+            assert_eq!(Some(local_event),res.next_char().unwrap());
+        }
+        assert_eq!(src.processed(),res.processed());
+
+        let mut src = BytesSource::strict(b"ok\xF0\x9F\x92!");
+        assert!(src.next_char().unwrap().is_some());
+        assert!(src.next_char().unwrap().is_some());
+        match src.next_char() {
+            Err(Error::InvalidUtf8 { local, .. }) => assert_eq!(local.bytes(),Snip { offset: 2, length: 3 }),
+            r => panic!("unexpected result: {:?}",r),
+        }
+        assert_eq!(src.processed(),Processed { chars: 2, bytes: 5, utf16: 2 });
+        assert_eq!(src.next_char().unwrap(),Some(SourceEvent::Char('!').localize(Snip { offset: 2, length: 1 },Snip { offset: 5, length: 1 })));
+        assert_eq!(src.next_char().unwrap(),None);
+    }
+}
@@ -1,11 +1,13 @@
 
-#[derive(Debug)]
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Entity {
     pub value: String,
     pub entity: Instance,
 }
 
 #[derive(Debug,Clone,Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum Instance {
     Char(char),
     Char2(char,char),
@@ -5,7 +5,8 @@ use super::{
 use crate::{
     ParserResult,
     Source, SourceEvent, ParserEvent, SourceResult, Local,
-    Parser, Runtime, PipeParser, IntoPipeParser,
+    Parser, Runtime, RuntimeState, PipeParser, IntoPipeParser,
+    Checkpoint, Diagnostics,
 };
 
 /*
@@ -31,18 +32,23 @@ use crate::{
 
 #[derive(Debug,Clone)]
 pub struct Builder {
-
+    diagnostics: Option<Diagnostics>,
 }
 impl Builder {
     pub fn new() -> Builder {
-        Builder { }
+        Builder { diagnostics: None }
+    }
+    // unknown entities, invalid code points and unterminated numeric entities are reported into the sink
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Builder {
+        self.diagnostics = Some(diagnostics);
+        self
     }
     pub fn create(self) -> EntityParser {
-        EntityParser(Runtime::new(()))
+        EntityParser(Runtime::new(self.diagnostics))
     }
 }
 
-pub struct EntityParser(Runtime<EntityState,Entity,()>);
+pub struct EntityParser(Runtime<EntityState,Entity,Option<Diagnostics>>);
 impl Parser for EntityParser {
     type Data = Entity;
     
@@ -51,6 +57,28 @@ impl Parser for EntityParser {
     }
 }
 
+// checkpoint of both EntityParser and PipedEntityParser
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct EntityCheckpoint {
+    runtime: RuntimeState<EntityState,Entity>,
+    tmp: Option<Local<SourceEvent>>,
+}
+
+impl Checkpoint for EntityParser {
+    type State = EntityCheckpoint;
+
+    fn checkpoint(&self) -> Option<EntityCheckpoint> {
+        Some(EntityCheckpoint {
+            runtime: self.0.checkpoint()?,
+            tmp: None,
+        })
+    }
+    fn restore(&mut self, state: EntityCheckpoint) {
+        self.0.restore(state.runtime);
+    }
+}
+
 impl IntoPipeParser for EntityParser {
     type Piped = PipedEntityParser;
     
@@ -63,10 +91,25 @@ impl IntoPipeParser for EntityParser {
 }
 
 pub struct PipedEntityParser {
-    parser: Runtime<EntityState,Entity,()>,
+    parser: Runtime<EntityState,Entity,Option<Diagnostics>>,
     tmp: Option<Local<SourceEvent>>,
 }
 
+impl Checkpoint for PipedEntityParser {
+    type State = EntityCheckpoint;
+
+    fn checkpoint(&self) -> Option<EntityCheckpoint> {
+        Some(EntityCheckpoint {
+            runtime: self.parser.checkpoint()?,
+            tmp: self.tmp,
+        })
+    }
+    fn restore(&mut self, state: EntityCheckpoint) {
+        self.parser.restore(state.runtime);
+        self.tmp = state.tmp;
+    }
+}
+
 impl PipeParser for PipedEntityParser {
     fn next_char<S: Source>(&mut self, src: &mut S) -> SourceResult {
         Ok(match self.tmp.take() {
@@ -6,10 +6,13 @@ use crate::{
     Error, Local, ParserEvent,
     NextResult, Next, StateMachine,
     SourceEvent, Breaker,
+    Diagnostics, Warning,
+    diagnostics::warn,
 };
 
 
-#[derive(Debug)]
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub(in super) enum EntityState {
     // Entities
     Init,
@@ -25,7 +28,8 @@ impl Default for EntityState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub(in super) struct ReadEntity {
     begin: Local<char>,
     current: Local<char>,
@@ -33,42 +37,52 @@ pub(in super) struct ReadEntity {
     chars: Vec<Local<char>>,
 }
 impl ReadEntity {
-    fn named_into_state(self) -> NextResult<EntityState,Entity> {
+    fn local(&self) -> Local<()> {
+        match Local::from_segment(self.begin,self.current) {
+            Ok(local) => local.local(()),
+            Err(_) => self.begin.local(()),
+        }
+    }
+    fn named_into_state(self, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {
         let mut ns = Next::empty();
         match ENTITIES.get(&self.content) {
             Some(e) => ns = ns.with_event(create_entity_event(self,e)?),
-            None => for c in self.chars {
-                ns = ns.with_event(c.map(|c| ParserEvent::Char(c)));
+            None => {
+                warn(diagnostics,self.local(),|| Warning::UnknownEntity(self.content.clone()));
+                for c in self.chars {
+                    ns = ns.with_event(c.map(|c| ParserEvent::Char(c)));
+                }
             },
         }
         Ok(ns)
     }
-    fn number_into_state(self) -> NextResult<EntityState,Entity> {
-        let mut ns = Next::empty();
-        match match u32::from_str_radix(&self.content,10) {
-            Ok(u) => char::from_u32(u),
-            Err(_) => None,
-        } {
-            Some(e) => ns = ns.with_event(create_entity_event(self,Instance::Char(e))?),
-            None => for c in self.chars {
-                ns = ns.with_event(c.map(|c| ParserEvent::Char(c)));
-            },
-        }
-        Ok(ns)
+    fn number_into_state(self, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {
+        self.code_point_into_state(10,diagnostics)
     }
-    fn number_x_into_state(self) -> NextResult<EntityState,Entity> {
+    fn number_x_into_state(self, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {
+        self.code_point_into_state(16,diagnostics)
+    }
+    fn code_point_into_state(self, radix: u32, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {
         let mut ns = Next::empty();
-        match match u32::from_str_radix(&self.content,16) {
+        match match u32::from_str_radix(&self.content,radix) {
             Ok(u) => char::from_u32(u),
             Err(_) => None,
         } {
             Some(e) => ns = ns.with_event(create_entity_event(self,Instance::Char(e))?),
-            None => for c in self.chars {
-                ns = ns.with_event(c.map(|c| ParserEvent::Char(c)));
+            None => {
+                warn(diagnostics,self.local(),|| Warning::InvalidCodePoint(self.chars.iter().map(|c| *c.data()).collect()));
+                for c in self.chars {
+                    ns = ns.with_event(c.map(|c| ParserEvent::Char(c)));
+                }
             },
         }
         Ok(ns)
     }
+    // numeric entity without ';'
+    fn unterminated_into_state(self, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {
+        warn(diagnostics,self.local(),|| Warning::UnterminatedEntity(self.chars.iter().map(|c| *c.data()).collect()));
+        self.failed_into_state()
+    }
     fn failed_into_state(self) -> NextResult<EntityState,Entity> {
         let mut ns = Next::empty();
         for c in self.chars {
@@ -79,10 +93,10 @@ impl ReadEntity {
 }
 
 impl StateMachine for EntityState {
-    type Context = ();
+    type Context = Option<Diagnostics>;
     type Data = Entity;
     
-    fn eof(self, _: &Self::Context) -> NextResult<EntityState,Entity> {        
+    fn eof(self, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {        
         Ok(match self {
             EntityState::Init => Next::empty(),
             EntityState::MayBeEntity(amp_char) => Next::empty().with_event(amp_char.map(|c| ParserEvent::Char(c))),
@@ -91,19 +105,19 @@ impl StateMachine for EntityState {
                     .with_event(amp_char.map(|c| ParserEvent::Char(c)))
                     .with_event(hash_char.map(|c| ParserEvent::Char(c)))
             },
-            EntityState::EntityNamed(ent) => ent.named_into_state()?,
+            EntityState::EntityNamed(ent) => ent.named_into_state(diagnostics)?,
             EntityState::EntityNumber(ent) |
-            EntityState::EntityNumberX(ent) => ent.failed_into_state()?,
+            EntityState::EntityNumberX(ent) => ent.unterminated_into_state(diagnostics)?,
         })
     }
-    fn next_state(self, local_src: Local<SourceEvent>,  _: &Self::Context) -> NextResult<EntityState,Entity> {
+    fn next_state(self, local_src: Local<SourceEvent>, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {
         match self {
             EntityState::Init => init(local_src),
             EntityState::MayBeEntity(amp_char) => may_be_entity(amp_char,local_src),
             EntityState::MayBeNumEntity(amp_char,hash_char) => may_be_num_entity(amp_char,hash_char,local_src),
-            EntityState::EntityNamed(ent) => entity_named(ent,local_src),
-            EntityState::EntityNumber(ent) => entity_number(ent,local_src),
-            EntityState::EntityNumberX(ent) => entity_number_x(ent,local_src),
+            EntityState::EntityNamed(ent) => entity_named(ent,local_src,diagnostics),
+            EntityState::EntityNumber(ent) => entity_number(ent,local_src,diagnostics),
+            EntityState::EntityNumberX(ent) => entity_number_x(ent,local_src,diagnostics),
         }
     }
 }
@@ -138,7 +152,7 @@ fn create_entity_event(entity: ReadEntity, replace: Instance) -> Result<Local<Pa
         })
 }
 
-fn entity_number_x(mut ent: ReadEntity, local_src: Local<SourceEvent>) -> NextResult<EntityState,Entity> {
+fn entity_number_x(mut ent: ReadEntity, local_src: Local<SourceEvent>, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {
     Ok(match *local_src.data() {
         SourceEvent::Char(lc) => {
             let local_char = local_src.local(lc);
@@ -152,21 +166,21 @@ fn entity_number_x(mut ent: ReadEntity, local_src: Local<SourceEvent>) -> NextRe
                 ';' => {
                     ent.current = local_char;
                     ent.chars.push(local_char);
-                    ent.number_x_into_state()?
+                    ent.number_x_into_state(diagnostics)?
                 },
-                '&'=> ent.failed_into_state()?.with_state(EntityState::MayBeEntity(local_char)),
-                _ => ent.failed_into_state()?.with_event(local_char.map(|c| ParserEvent::Char(c))),
+                '&'=> ent.unterminated_into_state(diagnostics)?.with_state(EntityState::MayBeEntity(local_char)),
+                _ => ent.unterminated_into_state(diagnostics)?.with_event(local_char.map(|c| ParserEvent::Char(c))),
             }
         },
         SourceEvent::Breaker(b) => match b {
             Breaker::None => Next::empty().with_state(EntityState::EntityNumberX(ent)),
-            _ => ent.failed_into_state()?
+            _ => ent.unterminated_into_state(diagnostics)?
                 .with_event(local_src.local(ParserEvent::Breaker(b))),
         },
     })
 }
 
-fn entity_number(mut ent: ReadEntity, local_src: Local<SourceEvent>) -> NextResult<EntityState,Entity> {
+fn entity_number(mut ent: ReadEntity, local_src: Local<SourceEvent>, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {
     Ok(match *local_src.data() {
         SourceEvent::Char(lc) => {
             let local_char = local_src.local(lc);
@@ -180,15 +194,15 @@ fn entity_number(mut ent: ReadEntity, local_src: Local<SourceEvent>) -> NextResu
                 ';' => {
                     ent.current = local_char;
                     ent.chars.push(local_char);
-                    ent.number_into_state()?                
+                    ent.number_into_state(diagnostics)?                
                 },
-                '&'=> ent.failed_into_state()?.with_state(EntityState::MayBeEntity(local_char)),
-                _ => ent.failed_into_state()?.with_event(local_char.map(|c| ParserEvent::Char(c))),
+                '&'=> ent.unterminated_into_state(diagnostics)?.with_state(EntityState::MayBeEntity(local_char)),
+                _ => ent.unterminated_into_state(diagnostics)?.with_event(local_char.map(|c| ParserEvent::Char(c))),
             }
         },
         SourceEvent::Breaker(b) => match b {
             Breaker::None => Next::empty().with_state(EntityState::EntityNumber(ent)),
-            _ => ent.failed_into_state()?
+            _ => ent.unterminated_into_state(diagnostics)?
                 .with_event(local_src.local(ParserEvent::Breaker(b))),
         },
     })
@@ -239,7 +253,7 @@ fn may_be_num_entity(amp_char: Local<char>, hash_char: Local<char>, local_src: L
     })
 }
 
-fn entity_named(mut ent: ReadEntity, local_src: Local<SourceEvent>) -> NextResult<EntityState,Entity> {
+fn entity_named(mut ent: ReadEntity, local_src: Local<SourceEvent>, diagnostics: &Option<Diagnostics>) -> NextResult<EntityState,Entity> {
     Ok(match *local_src.data() {
         SourceEvent::Char(lc) => {
             let local_char = local_src.local(lc);
@@ -258,15 +272,15 @@ fn entity_named(mut ent: ReadEntity, local_src: Local<SourceEvent>) -> NextResul
                     ent.current = local_char;
                     ent.content.push(*local_char.data());
                     ent.chars.push(local_char);
-                    ent.named_into_state()?                
+                    ent.named_into_state(diagnostics)?                
                 },
-                '&'=> ent.named_into_state()?.with_state(EntityState::MayBeEntity(local_char)),
-                _ => ent.named_into_state()?.with_event(local_char.map(|c| ParserEvent::Char(c))),
+                '&'=> ent.named_into_state(diagnostics)?.with_state(EntityState::MayBeEntity(local_char)),
+                _ => ent.named_into_state(diagnostics)?.with_event(local_char.map(|c| ParserEvent::Char(c))),
             }
         },
         SourceEvent::Breaker(b) => match b {
             Breaker::None => Next::empty().with_state(EntityState::EntityNamed(ent)),
-            _ => ent.named_into_state()?
+            _ => ent.named_into_state(diagnostics)?
                 .with_event(local_src.local(ParserEvent::Breaker(b))),
         },
     })
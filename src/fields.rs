@@ -0,0 +1,329 @@
+use crate::{
+    Snip, Local, Localize,
+    Source, SourceResult, SourceEvent, Processed,
+    Breaker, StrSource, IntoSource,
+    source::Shift,
+    SourceExt,
+    Parser, ParserEvent, Error,
+};
+
+/*
+
+   Multi-field document: fields (named or not) are processed as a single source,
+   the separator breaker is inserted between fields (zero-length, at the beginning of the next field),
+   empty fields are skipped.
+
+   Locals are global (as if fields were concatenated without separators),
+   FieldLayout maps them back to (field index, Local within the field).
+
+   Source events carry no field id themselves: parsers downstream merge locals (Local::from_segment)
+   and emit their own events, so an id attached to source events would not reach the output. Global offsets do,
+   so the field is attached to the output: InFields resolves every event with FieldLayout::locate
+   (the layout is fixed when the source is built and can be cloned before the source is piped):
+
+      let src = FieldsSource::new().with_field("title",title).with_field("body",body);
+      let mut parser = src.layout().parser(words::Builder::new().create());
+      let mut src = src.pipe(tagger::Builder::new().create().into_breaker());
+      while let Some(ev) = parser.next_event(&mut src)? {
+          ... ev.field, ev.local (within the field), ev.event (global) ...
+      }
+
+   The same for sources: src.layout().source(src.pipe(...)).next_char().
+
+   Zero-length locals at a field boundary (separators, positions) belong to the next non-empty field,
+   locals spanning several fields are clipped by the end of the first one.
+
+*/
+
+#[derive(Debug,Clone)]
+struct Field {
+    name: Option<String>,
+    start: Processed,
+    length: Processed,
+}
+
+#[derive(Debug,Clone,Default)]
+pub struct FieldLayout {
+    fields: Vec<Field>,
+}
+impl FieldLayout {
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+    pub fn name(&self, field: usize) -> Option<&str> {
+        self.fields.get(field).and_then(|f| f.name.as_deref())
+    }
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f.name.as_deref() == Some(name))
+    }
+    pub fn start(&self, field: usize) -> Option<Processed> {
+        self.fields.get(field).map(|f| f.start)
+    }
+
+    // parser with the field of every event
+    pub fn parser<P: Parser>(&self, parser: P) -> InFields<P> {
+        InFields { inner: parser, layout: self.clone() }
+    }
+    // source with the field of every event
+    pub fn source<S: Source>(&self, source: S) -> InFields<S> {
+        InFields { inner: source, layout: self.clone() }
+    }
+    // the event with its field, error if the event is out of the fields
+    pub fn attach<E>(&self, event: Local<E>) -> Result<InField<E>,Error> {
+        match self.locate(&event) {
+            Some((field,local)) => Ok(InField { field, local, event }),
+            None => Err(Error::InvalidInput { local: event.local(()), message: "event is out of the fields".to_string() }),
+        }
+    }
+
+    // field of the beginning of the local and the local in the field coordinates (clipped by the field end)
+    pub fn locate<E>(&self, local: &Local<E>) -> Option<(usize,Local<()>)> {
+        let offset = local.chars().offset;
+        let idx = self.fields.iter().rposition(|f| f.start.chars <= offset)?;
+        let field = &self.fields[idx];
+        if offset > field.start.chars + field.length.chars {
+            return None;
+        }
+        let clip = |snip: Snip, start: usize, length: usize| {
+            let offset = snip.offset - start;
+            Snip { offset, length: snip.length.min(length - offset) }
+        };
+        Some((idx,().localize_utf16(
+            clip(local.chars(),field.start.chars,field.length.chars),
+            clip(local.bytes(),field.start.bytes,field.length.bytes),
+            clip(local.utf16(),field.start.utf16,field.length.utf16),
+        )))
+    }
+}
+
+// event (with the global local) in the field: index and local within the field
+#[derive(Debug,Clone,PartialEq)]
+pub struct InField<E> {
+    pub field: usize,
+    pub local: Local<()>,
+    pub event: Local<E>,
+}
+
+pub struct InFields<T> {
+    inner: T,
+    layout: FieldLayout,
+}
+impl<T> InFields<T> {
+    pub fn layout(&self) -> &FieldLayout {
+        &self.layout
+    }
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+impl<S: Source> InFields<S> {
+    pub fn next_char(&mut self) -> Result<Option<InField<SourceEvent>>,Error> {
+        self.inner.next_char()?.map(|local_se| self.layout.attach(local_se)).transpose()
+    }
+}
+impl<P: Parser> InFields<P> {
+    pub fn next_event<S: Source>(&mut self, src: &mut S) -> Result<Option<InField<ParserEvent<P::Data>>>,Error> {
+        self.inner.next_event(src)?.map(|local_pe| self.layout.attach(local_pe)).transpose()
+    }
+}
+
+pub struct FieldsSource<'s> {
+    texts: Vec<&'s str>,
+    layout: FieldLayout,
+    separator: Breaker,
+    next: usize,
+    separated: bool,
+    current: Option<Shift<StrSource<'s>>>,
+    done: Processed,
+}
+impl<'s> Default for FieldsSource<'s> {
+    fn default() -> FieldsSource<'s> {
+        FieldsSource::new()
+    }
+}
+impl<'s> FieldsSource<'s> {
+    pub fn new() -> FieldsSource<'s> {
+        FieldsSource {
+            texts: Vec::new(),
+            layout: FieldLayout::default(),
+            separator: Breaker::Section,
+            next: 0,
+            separated: false,
+            current: None,
+            done: Processed::default(),
+        }
+    }
+    pub fn with_separator(mut self, separator: Breaker) -> FieldsSource<'s> {
+        self.separator = separator;
+        self
+    }
+    pub fn with_field(self, name: &str, text: &'s str) -> FieldsSource<'s> {
+        self.push(Some(name.to_string()),text)
+    }
+    pub fn with_text(self, text: &'s str) -> FieldsSource<'s> {
+        self.push(None,text)
+    }
+    pub fn layout(&self) -> &FieldLayout {
+        &self.layout
+    }
+
+    fn push(mut self, name: Option<String>, text: &'s str) -> FieldsSource<'s> {
+        let start = match self.layout.fields.last() {
+            Some(f) => Processed {
+                chars: f.start.chars + f.length.chars,
+                bytes: f.start.bytes + f.length.bytes,
+                utf16: f.start.utf16 + f.length.utf16,
+            },
+            None => Processed::default(),
+        };
+        self.layout.fields.push(Field {
+            name,
+            start,
+            length: Processed::of(text),
+        });
+        self.texts.push(text);
+        self
+    }
+}
+impl<'s> Source for FieldsSource<'s> {
+    fn next_char(&mut self) -> SourceResult {
+        loop {
+            match &mut self.current {
+                Some(src) => match src.next_char()? {
+                    Some(local_se) => return Ok(Some(local_se)),
+                    None => {
+                        self.done = src.processed();
+                        self.current = None;
+                    },
+                },
+                None => {
+                    let (text,field) = match (self.texts.get(self.next),self.layout.fields.get(self.next)) {
+                        (Some(text),Some(field)) => (*text,field),
+                        _ => return Ok(None),
+                    };
+                    if text.is_empty() {
+                        self.next += 1;
+                        continue;
+                    }
+                    if (self.done.chars > 0) && !self.separated {
+                        self.separated = true;
+                        return Ok(Some(SourceEvent::Breaker(self.separator).localize_utf16(
+                            Snip { offset: field.start.chars, length: 0 },
+                            Snip { offset: field.start.bytes, length: 0 },
+                            Snip { offset: field.start.utf16, length: 0 },
+                        )));
+                    }
+                    self.current = Some(text.into_source().with_offset(field.start));
+                    self.separated = false;
+                    self.next += 1;
+                },
+            }
+        }
+    }
+    fn processed(&self) -> Processed {
+        match &self.current {
+            Some(src) => src.processed(),
+            None => self.done,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn fields() {
+        let src = FieldsSource::new()
+            .with_field("title","Ёж")
+            .with_field("body","a&amp;b")
+            .with_text("")
+            .with_field("comment","💯!");
+        let layout = src.layout().clone();
+        assert_eq!(layout.len(),4);
+        assert_eq!(layout.index("body"),Some(1));
+        assert_eq!(layout.name(2),None);
+
+        let mut src = layout.source(src.pipe(entities::Builder::new().create().into_piped()));
+
+        let mut res_iter = [
+            (SourceEvent::Char('Ё'),0,Snip { offset: 0, length: 1 },Snip { offset: 0, length: 2 }),
+            (SourceEvent::Char('ж'),0,Snip { offset: 1, length: 1 },Snip { offset: 2, length: 2 }),
+            (SourceEvent::Breaker(Breaker::Section),1,Snip { offset: 0, length: 0 },Snip { offset: 0, length: 0 }),
+            (SourceEvent::Char('a'),1,Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            (SourceEvent::Char('&'),1,Snip { offset: 1, length: 5 },Snip { offset: 1, length: 5 }),
+            (SourceEvent::Char('b'),1,Snip { offset: 6, length: 1 },Snip { offset: 6, length: 1 }),
+            (SourceEvent::Breaker(Breaker::Section),3,Snip { offset: 0, length: 0 },Snip { offset: 0, length: 0 }),
+            (SourceEvent::Char('💯'),3,Snip { offset: 0, length: 1 },Snip { offset: 0, length: 4 }),
+            (SourceEvent::Char('!'),3,Snip { offset: 1, length: 1 },Snip { offset: 4, length: 1 }),
+        ].into_iter();
+
+        while let Some(ev) = src.next_char().unwrap() {
+            match res_iter.next() {
+                Some((se,rfield,chars,bytes)) => {
+                    println!("Source: {:?}",ev);
+                    assert_eq!(*ev.event.data(),se);
+                    assert_eq!(ev.field,rfield);
+                    assert_eq!(ev.local.chars(),chars);
+                    assert_eq!(ev.local.bytes(),bytes);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+        assert_eq!(src.into_inner().processed(),Processed { chars: 11, bytes: 16, utf16: 12 });
+    }
+
+    #[test]
+    fn parser() {
+        let src = FieldsSource::new()
+            .with_field("title","Ёж")
+            .with_field("body","<b>a</b> bc");
+        let mut parser = src.layout().parser(words::Builder::new().create());
+        let mut src = src.pipe(tagger::Builder::new().create().into_breaker());
+
+        let mut res = Vec::new();
+        while let Some(ev) = parser.next_event(&mut src).unwrap() {
+            if let ParserEvent::Parsed(word) = ev.event.data() {
+                res.push((word.text.clone(),ev.field,ev.local.chars(),ev.event.chars().offset));
+            }
+        }
+        assert_eq!(res,vec![
+            ("Ёж".to_string(),0,Snip { offset: 0, length: 2 },0),
+            ("a".to_string(),1,Snip { offset: 3, length: 1 },5),
+            (" ".to_string(),1,Snip { offset: 8, length: 1 },10),
+            ("bc".to_string(),1,Snip { offset: 9, length: 2 },11),
+        ]);
+    }
+
+    #[test]
+    fn locate() {
+        let mut src = FieldsSource::new()
+            .with_field("title","Ab")
+            .with_text("")
+            .with_field("body","cd");
+        let layout = src.layout().clone();
+        let locate = |chars: Snip, bytes: Snip| layout.locate(&().localize(chars,bytes)).map(|(field,local)| (field,local.chars()));
+
+        // separator, zero-length at the beginning of "body"
+        let mut events = Vec::new();
+        while let Some(local_se) = src.next_char().unwrap() {
+            events.push(local_se);
+        }
+        let separator = events[2];
+        assert_eq!(*separator.data(),SourceEvent::Breaker(Breaker::Section));
+        assert_eq!(separator.chars(),Snip { offset: 2, length: 0 });
+        assert_eq!(locate(separator.chars(),separator.bytes()),Some((2,Snip { offset: 0, length: 0 })));
+
+        assert_eq!(locate(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 1 }),Some((0,Snip { offset: 1, length: 1 })));
+        assert_eq!(locate(Snip { offset: 1, length: 2 },Snip { offset: 1, length: 2 }),Some((0,Snip { offset: 1, length: 1 })));
+        assert_eq!(locate(Snip { offset: 4, length: 0 },Snip { offset: 4, length: 0 }),Some((2,Snip { offset: 2, length: 0 })));
+        assert_eq!(locate(Snip { offset: 5, length: 0 },Snip { offset: 5, length: 0 }),None);
+    }
+}
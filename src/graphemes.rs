@@ -0,0 +1,233 @@
+use unicode_segmentation::GraphemeCursor;
+
+use crate::{
+    Local,
+    Source, SourceEvent, Processed,
+    ParserEvent, ParserResult,
+    Parser, Runtime,
+    NextResult, Next, StateMachine,
+    RuntimeState, Checkpoint,
+};
+
+/*
+
+   Extended grapheme clusters (UAX #29): https://www.unicode.org/reports/tr29/#Grapheme_Cluster_Boundaries
+
+   Chars are grouped into clusters, each cluster is a single ParserEvent::Parsed(Grapheme),
+   its Local spans all the code points of the cluster (entities included: "e&#x301;" is a single cluster).
+   Breakers are passed as they are and always end the current cluster.
+
+   Grapheme::index is an offset of the cluster in graphemes (breakers are not counted).
+
+   GraphemeParser is a parser as any other, Graphemes (src.graphemes()) is the parser bound to its source.
+
+*/
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Grapheme {
+    pub text: String,
+    pub index: usize,
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+struct Cluster {
+    text: String,
+    begin: Local<()>,
+    end: Local<()>,
+}
+
+#[derive(Debug,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub(crate) struct GraphemeState {
+    current: Option<Cluster>,
+    count: usize,
+}
+impl GraphemeState {
+    fn emit(&mut self, next: &mut Next<GraphemeState,Grapheme>) -> Result<(),crate::Error> {
+        if let Some(cluster) = self.current.take() {
+            let grapheme = Grapheme {
+                text: cluster.text,
+                index: self.count,
+            };
+            self.count += 1;
+            next.push_event(Local::from_segment(cluster.begin,cluster.end)?.local(ParserEvent::Parsed(grapheme)));
+        }
+        Ok(())
+    }
+}
+
+impl StateMachine for GraphemeState {
+    type Context = ();
+    type Data = Grapheme;
+
+    fn eof(mut self, _props: &()) -> NextResult<GraphemeState,Grapheme> {
+        let mut next = Next::empty();
+        self.emit(&mut next)?;
+        Ok(next)
+    }
+    fn next_state(mut self, local_src: Local<SourceEvent>, _props: &()) -> NextResult<GraphemeState,Grapheme> {
+        let mut next = Next::empty();
+        let (local,se) = local_src.into_inner();
+        match se {
+            SourceEvent::Char(c) => {
+                if let Some(cluster) = &mut self.current && !extend(&mut cluster.text,c) {
+                    cluster.end = local;
+                } else {
+                    self.emit(&mut next)?;
+                    self.current = Some(Cluster {
+                        text: c.to_string(),
+                        begin: local,
+                        end: local,
+                    });
+                }
+            },
+            SourceEvent::Breaker(b) => {
+                self.emit(&mut next)?;
+                next.push_event(local.local(ParserEvent::Breaker(b)));
+            },
+        }
+        Ok(next.with_state(self))
+    }
+}
+
+pub struct GraphemeParser(Runtime<GraphemeState,Grapheme,()>);
+
+impl Default for GraphemeParser {
+    fn default() -> GraphemeParser {
+        GraphemeParser::new()
+    }
+}
+impl GraphemeParser {
+    pub fn new() -> GraphemeParser {
+        GraphemeParser(Runtime::new(()))
+    }
+}
+
+impl Parser for GraphemeParser {
+    type Data = Grapheme;
+
+    fn next_event<S: Source>(&mut self, src: &mut S) -> ParserResult<Grapheme> {
+        self.0.next_event(src)
+    }
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct GraphemeCheckpoint(RuntimeState<GraphemeState,Grapheme>);
+
+impl Checkpoint for GraphemeParser {
+    type State = GraphemeCheckpoint;
+
+    fn checkpoint(&self) -> Option<GraphemeCheckpoint> {
+        self.0.checkpoint().map(GraphemeCheckpoint)
+    }
+    fn restore(&mut self, state: GraphemeCheckpoint) {
+        self.0.restore(state.0);
+    }
+}
+
+pub struct Graphemes<S> {
+    source: S,
+    parser: GraphemeParser,
+}
+impl<S: Source> Graphemes<S> {
+    pub(crate) fn new(source: S) -> Graphemes<S> {
+        Graphemes {
+            source,
+            parser: GraphemeParser::new(),
+        }
+    }
+    pub fn processed(&self) -> Processed {
+        self.source.processed()
+    }
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+
+    pub fn next_event(&mut self) -> ParserResult<Grapheme> {
+        self.parser.next_event(&mut self.source)
+    }
+}
+
+impl<S: Checkpoint> Checkpoint for Graphemes<S> {
+    type State = (S::State,GraphemeCheckpoint);
+
+    fn checkpoint(&self) -> Option<Self::State> {
+        Some((self.source.checkpoint()?,self.parser.checkpoint()?))
+    }
+    fn restore(&mut self, (source,parser): Self::State) {
+        self.source.restore(source);
+        self.parser.restore(parser);
+    }
+}
+
+// pushes c into the cluster text, returns true (and leaves text untouched) if there is a boundary before c
+fn extend(text: &mut String, c: char) -> bool {
+    let len = text.len();
+    text.push(c);
+    let boundary = GraphemeCursor::new(len,text.len(),true)
+        .is_boundary(text,0)
+        .unwrap_or(true);
+    if boundary {
+        text.truncate(len);
+    }
+    boundary
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn check<S: Source>(mut src: Graphemes<S>, result: Vec<Local<ParserEvent<Grapheme>>>) {
+        let mut res_iter = result.into_iter();
+        while let Some(local_event) = src.next_event().unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Parser: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+    }
+
+    fn grapheme(text: &str, index: usize) -> ParserEvent<Grapheme> {
+        ParserEvent::Parsed(Grapheme { text: text.to_string(), index })
+    }
+
+    #[test]
+    fn clusters() {
+        let src = "e\u{301}👨\u{200d}👩 🇷🇺🇺🇸"
+            .into_source()
+            .into_separator()
+            .graphemes();
+
+        check(src,vec![
+            grapheme("e\u{301}",0).localize_utf16(Snip { offset: 0, length: 2 },Snip { offset: 0, length: 3 },Snip { offset: 0, length: 2 }),
+            grapheme("👨\u{200d}👩",1).localize_utf16(Snip { offset: 2, length: 3 },Snip { offset: 3, length: 11 },Snip { offset: 2, length: 5 }),
+            ParserEvent::Breaker(Breaker::Space).localize_utf16(Snip { offset: 5, length: 1 },Snip { offset: 14, length: 1 },Snip { offset: 7, length: 1 }),
+            grapheme("🇷🇺",2).localize_utf16(Snip { offset: 6, length: 2 },Snip { offset: 15, length: 8 },Snip { offset: 8, length: 4 }),
+            grapheme("🇺🇸",3).localize_utf16(Snip { offset: 8, length: 2 },Snip { offset: 23, length: 8 },Snip { offset: 12, length: 4 }),
+        ]);
+    }
+
+    #[test]
+    fn entities() {
+        let src = "&#x1F468;&#x200D;&#x1F469;!"
+            .into_source()
+            .pipe(crate::entities::Builder::new().create().into_piped())
+            .graphemes();
+
+        check(src,vec![
+            grapheme("👨\u{200d}👩",0).localize(Snip { offset: 0, length: 26 },Snip { offset: 0, length: 26 }),
+            grapheme("!",1).localize(Snip { offset: 26, length: 1 },Snip { offset: 26, length: 1 }),
+        ]);
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    ParserResult,
+    Source,
+    Parser, Runtime, Breaker,
+    SourceEvent,ParserEvent,
+    PipeParser, SourceResult,
+    RuntimeState, Checkpoint,
+};
+
+use super::{
+    state::{HeadState,Heading},
+};
+
+/*
+
+   Plain-text headings, lines are separated by '\n' or Breaker::Line (Paragraph and Section breakers
+   separate lines as a blank line does):
+
+       Title                  Title
+       =====  (level 1)       -----  (level 2)        underlined, a blank line before
+
+       1. Title (level 1)     1.2.3 Title (level 3)   numbered (a single number with a dot), the title is capitalized without trailing punctuation
+       Глава 5, Chapter IV    (level 1)               chapter keyword and an arabic or roman number
+       INTRODUCTION           (level 1)               all caps, 4 letters or more with a word of 3 (not "USA", "II")
+       * * *, ***, ---        (level 0)               scene break
+
+   Numbered, chapter, all caps headings and scene breaks are standalone lines (blank lines before and after).
+
+   Heading is ParserEvent::Parsed(Heading) spanning the heading (with the underline), followed by the heading
+   events as they are. As a pipe parser the heading is a zero-length Breaker::Section at the heading beginning.
+
+*/
+
+const KEYWORDS: &[&str] = &["chapter","part","section","book","глава","часть","раздел","книга"];
+
+#[derive(Debug,Clone,Default)]
+pub struct Builder {
+    properties: HeadingProperties,
+}
+impl Builder {
+    pub fn new() -> Builder {
+        Builder{
+            properties: HeadingProperties::default(),
+        }
+    }
+    // longer lines are not headings (80 chars by default)
+    pub fn with_max_length(mut self, chars: usize) -> Builder {
+        self.properties.max_length = chars;
+        self
+    }
+    // chapter keyword, case-insensitive: "Глава" of "Глава 5"
+    pub fn with_keyword(mut self, keyword: &str) -> Builder {
+        self.properties.keywords.push(keyword.to_lowercase());
+        self
+    }
+    pub fn create(self) -> Headings {
+        Headings(Runtime::new(self.properties))
+    }
+}
+
+#[derive(Debug,Clone)]
+pub(in super) struct HeadingProperties {
+    pub(in super) max_length: usize,
+    keywords: Vec<String>,
+}
+impl Default for HeadingProperties {
+    fn default() -> HeadingProperties {
+        HeadingProperties {
+            max_length: 80,
+            keywords: KEYWORDS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+impl HeadingProperties {
+    pub(in super) fn is_keyword(&self, word: &str) -> bool {
+        let word = word.to_lowercase();
+        self.keywords.contains(&word)
+    }
+}
+
+
+pub struct Headings(Runtime<HeadState,Heading,HeadingProperties>);
+
+impl Parser for Headings {
+    type Data = Heading;
+
+    fn next_event<S: Source>(&mut self, src: &mut S) -> ParserResult<Heading> {
+        self.0.next_event(src)
+    }
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct HeadingCheckpoint(RuntimeState<HeadState,Heading>);
+
+impl Checkpoint for Headings {
+    type State = HeadingCheckpoint;
+
+    fn checkpoint(&self) -> Option<HeadingCheckpoint> {
+        self.0.checkpoint().map(HeadingCheckpoint)
+    }
+    fn restore(&mut self, state: HeadingCheckpoint) {
+        self.0.restore(state.0);
+    }
+}
+
+impl PipeParser for Headings {
+    fn next_char<S: Source>(&mut self, src: &mut S) -> SourceResult {
+        Ok(match self.next_event(src)? {
+            Some(local_pe) => {
+                let (local,pe) = local_pe.into_inner();
+                Some(match pe {
+                    ParserEvent::Char(c) => local.local(SourceEvent::Char(c)),
+                    ParserEvent::Breaker(b) => local.local(SourceEvent::Breaker(b)),
+                    ParserEvent::Parsed(_) => local.local(SourceEvent::Breaker(Breaker::Section)).into_position(),
+                })
+            },
+            None => None,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use super::*;
+    use super::super::state::HeadingKind;
+
+    // text with "[level]" before headings
+    fn annotated<S: Source>(builder: Builder, mut src: S) -> String {
+        let mut parser = builder.create();
+        let mut res = String::new();
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            match local_event.data() {
+                ParserEvent::Char(c) => res.push(*c),
+                ParserEvent::Breaker(Breaker::None) => {},
+                ParserEvent::Breaker(Breaker::Line) => res.push('\n'),
+                ParserEvent::Breaker(_) => res.push(' '),
+                ParserEvent::Parsed(heading) => res.push_str(&format!("[{}]",heading.level)),
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn basic() {
+        let mut src = "Ab\n===\n\nx".into_source();
+        let mut parser = Builder::new().create();
+
+        let mut res_iter = [
+            ParserEvent::Parsed(Heading { level: 1, kind: HeadingKind::Underlined }).localize(Snip { offset: 0, length: 6 },Snip { offset: 0, length: 6 }),
+            ParserEvent::Char('A').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            ParserEvent::Char('b').localize(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 1 }),
+            ParserEvent::Char('\n').localize(Snip { offset: 2, length: 1 },Snip { offset: 2, length: 1 }),
+            ParserEvent::Char('=').localize(Snip { offset: 3, length: 1 },Snip { offset: 3, length: 1 }),
+            ParserEvent::Char('=').localize(Snip { offset: 4, length: 1 },Snip { offset: 4, length: 1 }),
+            ParserEvent::Char('=').localize(Snip { offset: 5, length: 1 },Snip { offset: 5, length: 1 }),
+            ParserEvent::Char('\n').localize(Snip { offset: 6, length: 1 },Snip { offset: 6, length: 1 }),
+            ParserEvent::Char('\n').localize(Snip { offset: 7, length: 1 },Snip { offset: 7, length: 1 }),
+            ParserEvent::Char('x').localize(Snip { offset: 8, length: 1 },Snip { offset: 8, length: 1 }),
+        ].into_iter();
+
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Parser: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn markers() {
+        let text = "Title\n=====\nText.\n\nSub\n---\n\n1. Intro\n\n1.2.3 Deep Part\n\n1. Buy milk.\n\nГлава 5\n\nCHAPTER IV\n\nINTRODUCTION\n\n* * *\n\nPlain Text\n\nNOT ALONE\nnext";
+        assert_eq!(annotated(Builder::new(),text.into_source()),
+                   "[1]Title\n=====\nText.\n\n[2]Sub\n---\n\n[1]1. Intro\n\n[3]1.2.3 Deep Part\n\n1. Buy milk.\n\n[1]Глава 5\n\n[1]CHAPTER IV\n\n[1]INTRODUCTION\n\n[0]* * *\n\nPlain Text\n\nNOT ALONE\nnext");
+        assert_eq!(annotated(Builder::new(),"Title\n===\nINTRO\n\nx".into_source()),"[1]Title\n===\nINTRO\n\nx");
+        assert_eq!(annotated(Builder::new(),"2024 Was great\n\nUSA\n\nII\n\nTHE END".into_source()),"2024 Was great\n\nUSA\n\nII\n\n[1]THE END");
+        assert_eq!(annotated(Builder::new().with_keyword("Акт"),"Акт 2\n\nтекст".into_source()),"[1]Акт 2\n\nтекст");
+        assert_eq!(annotated(Builder::new().with_max_length(5),"LONG TITLE\n\nSHORT".into_source()),"LONG TITLE\n\n[1]SHORT");
+
+        // long lines are passed as they go
+        let long = "a".repeat(500);
+        assert_eq!(annotated(Builder::new(),format!("{long}\n===\n\nx").as_str().into_source()),format!("{long}\n===\n\nx"));
+        let long = "A ".repeat(100);
+        assert_eq!(annotated(Builder::new(),format!("INTRO\n\n{long}\n\nTHE END").as_str().into_source()),format!("[1]INTRO\n\n{long}\n\n[1]THE END"));
+        assert_eq!(annotated(Builder::new(),format!("INTRO\n{long}\n\nTHE END").as_str().into_source()),format!("INTRO\n{long}\n\n[1]THE END"));
+        let blank = " ".repeat(100);
+        assert_eq!(annotated(Builder::new(),format!("INTRO\n{blank}\nTHE END").as_str().into_source()),format!("[1]INTRO\n{blank}\n[1]THE END"));
+    }
+
+    #[test]
+    fn piped() {
+        let mut src = "<p>INTRODUCTION</p><p>Text</p>".into_source()
+            .pipe(tagger::Builder::new().create().into_breaker())
+            .pipe(Builder::new().create());
+        let mut res = Vec::new();
+        while let Some(local_se) = src.next_char().unwrap() {
+            res.push(*local_se.data());
+        }
+        assert_eq!(res,vec![
+            SourceEvent::Breaker(Breaker::Paragraph),
+            SourceEvent::Breaker(Breaker::Section),
+            SourceEvent::Char('I'), SourceEvent::Char('N'), SourceEvent::Char('T'), SourceEvent::Char('R'),
+            SourceEvent::Char('O'), SourceEvent::Char('D'), SourceEvent::Char('U'), SourceEvent::Char('C'),
+            SourceEvent::Char('T'), SourceEvent::Char('I'), SourceEvent::Char('O'), SourceEvent::Char('N'),
+            SourceEvent::Breaker(Breaker::Paragraph),
+            SourceEvent::Breaker(Breaker::Paragraph),
+            SourceEvent::Char('T'), SourceEvent::Char('e'), SourceEvent::Char('x'), SourceEvent::Char('t'),
+            SourceEvent::Breaker(Breaker::Paragraph),
+        ]);
+    }
+}
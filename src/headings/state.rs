@@ -0,0 +1,266 @@
+use crate::{
+    Local, ParserEvent,
+    NextResult, Next, StateMachine,
+    SourceEvent, Breaker,
+};
+
+use super::parser::HeadingProperties;
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum HeadingKind {
+    Underlined,  // setext: "Title\n=====" (level 1), "Title\n-----" (level 2)
+    Numbered,    // "1. Title" (level 1), "1.2.3 Title" (level 3)
+    Chapter,     // "Глава 5", "Chapter IV. Title" (level 1)
+    Caps,        // "INTRODUCTION" (level 1)
+    SceneBreak,  // "* * *", "***", "---" (level 0, no title)
+}
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Heading {
+    pub level: usize,
+    pub kind: HeadingKind,
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub(in super) struct Line {
+    events: Vec<Local<SourceEvent>>,
+    end: Option<Local<SourceEvent>>,  // '\n' or a breaker (Line, Paragraph, Section), None at the end of text
+}
+impl Line {
+    fn text(&self) -> String {
+        let mut s = String::new();
+        for lse in &self.events {
+            match lse.data() {
+                SourceEvent::Char(c) => s.push(*c),
+                SourceEvent::Breaker(Breaker::None) => {},
+                SourceEvent::Breaker(_) => s.push(' '),
+            }
+        }
+        s.trim().to_string()
+    }
+    fn is_blank(&self) -> bool {
+        self.text().is_empty()
+    }
+    // hard breakers separate lines as a blank line does
+    fn is_hard(&self) -> bool {
+        matches!(self.end.map(|lse| *lse.data()), Some(SourceEvent::Breaker(Breaker::Paragraph)) | Some(SourceEvent::Breaker(Breaker::Section)))
+    }
+    fn underline(&self) -> Option<usize> {
+        let text = self.text();
+        match text.chars().count() >= 3 {
+            true if text.chars().all(|c| c == '=') => Some(1),
+            true if text.chars().all(|c| c == '-') => Some(2),
+            _ => None,
+        }
+    }
+    fn push_into(self, next: &mut Next<HeadState,Heading>) {
+        for lse in self.events.into_iter().chain(self.end) {
+            next.push_event(lse.map(|se| se.into()));
+        }
+    }
+}
+
+fn scene_break(text: &str) -> bool {
+    let mut marks = text.chars().filter(|c| !c.is_whitespace());
+    match marks.next() {
+        Some(first @ ('*' | '-' | '_' | '~' | '=' | '#')) => (marks.clone().count() >= 2) && marks.all(|c| c == first),
+        _ => false,
+    }
+}
+
+// level of "1.2.3 Title" or "1. Title" (not "2024 Title")
+fn numbered(text: &str) -> Option<usize> {
+    let (number,title) = text.split_once(char::is_whitespace)?;
+    let parts = match number.strip_suffix('.') {
+        Some(parts) => parts,
+        None if number.contains('.') => number,
+        None => return None,
+    };
+    let mut level = 0;
+    for part in parts.split('.') {
+        match !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) {
+            true => level += 1,
+            false => return None,
+        }
+    }
+    let title = title.trim_start();
+    match (title.chars().next(),title.chars().last()) {
+        (Some(first),Some(last)) if first.is_uppercase() && !matches!(last, '.' | ',' | ';' | ':') => Some(level),
+        _ => None,
+    }
+}
+
+// "Глава 5", "Chapter IV. Title"
+fn chapter(text: &str, props: &HeadingProperties) -> bool {
+    let mut words = text.split_whitespace();
+    match (words.next(),words.next()) {
+        (Some(keyword),Some(number)) => {
+            let number = number.trim_end_matches(['.',':']);
+            props.is_keyword(keyword) && !number.is_empty() &&
+                (number.chars().all(|c| c.is_ascii_digit()) || number.chars().all(|c| matches!(c, 'I' | 'V' | 'X' | 'L' | 'C' | 'D' | 'M')))
+        },
+        _ => false,
+    }
+}
+
+// at least 4 letters and a word of 3 letters: not "USA", "II"
+fn caps(text: &str) -> bool {
+    (text.chars().filter(|c| c.is_alphabetic()).count() >= 4) &&
+        text.split(|c: char| !c.is_alphabetic()).any(|word| word.chars().count() >= 3) &&
+        !text.chars().any(char::is_lowercase) &&
+        !matches!(text.chars().last(), Some(',' | ';'))
+}
+
+fn classify(line: &Line, props: &HeadingProperties) -> Option<Heading> {
+    let text = line.text();
+    if text.chars().count() > props.max_length {
+        return None;
+    }
+    if scene_break(&text) {
+        return Some(Heading { level: 0, kind: HeadingKind::SceneBreak });
+    }
+    if chapter(&text,props) {
+        return Some(Heading { level: 1, kind: HeadingKind::Chapter });
+    }
+    if let Some(level) = numbered(&text) {
+        return Some(Heading { level, kind: HeadingKind::Numbered });
+    }
+    if caps(&text) {
+        return Some(Heading { level: 1, kind: HeadingKind::Caps });
+    }
+    None
+}
+
+fn push_heading(heading: Heading, lines: Vec<Line>, next: &mut Next<HeadState,Heading>) -> Result<(),crate::Error> {
+    let first = lines.iter().flat_map(|line| line.events.iter()).next();
+    let last = lines.iter().flat_map(|line| line.events.iter()).last();
+    if let (Some(first),Some(last)) = (first,last) {
+        next.push_event(Local::from_segment(*first,*last)?.local(ParserEvent::Parsed(heading)));
+    }
+    for line in lines {
+        line.push_into(next);
+    }
+    Ok(())
+}
+
+/*
+
+   Headings are standalone lines (blank lines, hard breakers or text bounds before and after),
+   except underlined ones, which need a blank line before only.
+
+   A line is decided when the next line is read: the line is passed as it is, or preceded by
+   ParserEvent::Parsed(Heading) spanning the heading (with the underline).
+
+   A line longer than max_length events (whitespace included) is never a heading (nor an underline):
+   it is passed as it goes, it is blank if its first max_length events are whitespace.
+
+*/
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub(in super) struct HeadState {
+    blank_before: bool,          // line before `pending` is blank
+    pending: Option<Line>,       // complete line waiting for the next one
+    current: Vec<Local<SourceEvent>>,
+    long: Option<bool>,          // `current` is too long and is passed as it goes: blank or not
+}
+impl Default for HeadState {
+    fn default() -> HeadState {
+        HeadState {
+            blank_before: true,
+            pending: None,
+            current: Vec::new(),
+            long: None,
+        }
+    }
+}
+impl HeadState {
+    // decides the pending line
+    fn decide(&mut self, blank_after: bool, props: &HeadingProperties, next: &mut Next<HeadState,Heading>) -> Result<(),crate::Error> {
+        if let Some(line) = self.pending.take() {
+            let heading = match self.blank_before && blank_after && !line.is_blank() {
+                true => classify(&line,props),
+                false => None,
+            };
+            self.blank_before = line.is_blank() || line.is_hard();
+            match heading {
+                Some(heading) => push_heading(heading,vec![line],next)?,
+                None => line.push_into(next),
+            }
+        }
+        Ok(())
+    }
+    fn line(&mut self, line: Line, props: &HeadingProperties, next: &mut Next<HeadState,Heading>) -> Result<(),crate::Error> {
+        let underlined = match &self.pending {
+            Some(pending) if self.blank_before && !pending.is_blank() && !pending.is_hard() &&
+                (pending.text().chars().count() <= props.max_length) => line.underline(),
+            _ => None,
+        };
+        match (underlined,self.pending.take()) {
+            (Some(level),Some(pending)) => {
+                self.blank_before = line.is_hard();
+                push_heading(Heading { level, kind: HeadingKind::Underlined },vec![pending,line],next)?;
+            },
+            (_,pending) => {
+                self.pending = pending;
+                self.decide(line.is_blank(),props,next)?;
+                let hard = line.is_hard();
+                self.pending = Some(line);
+                if hard {
+                    self.decide(true,props,next)?;
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+impl StateMachine for HeadState {
+    type Context = HeadingProperties;
+    type Data = Heading;
+
+    fn eof(mut self, props: &HeadingProperties) -> NextResult<HeadState,Heading> {
+        let mut next = Next::empty();
+        if !self.current.is_empty() {
+            let line = Line { events: std::mem::take(&mut self.current), end: None };
+            self.line(line,props,&mut next)?;
+        }
+        self.decide(true,props,&mut next)?;
+        Ok(next)
+    }
+    fn next_state(mut self, local_src: Local<SourceEvent>, props: &HeadingProperties) -> NextResult<HeadState,Heading> {
+        let mut next = Next::empty();
+        match *local_src.data() {
+            SourceEvent::Char('\n') |
+            SourceEvent::Breaker(Breaker::Line) |
+            SourceEvent::Breaker(Breaker::Paragraph) |
+            SourceEvent::Breaker(Breaker::Section) => match self.long.take() {
+                Some(blank) => {
+                    let line = Line { events: Vec::new(), end: Some(local_src) };
+                    self.blank_before = blank || line.is_hard();
+                    line.push_into(&mut next);
+                },
+                None => {
+                    let line = Line { events: std::mem::take(&mut self.current), end: Some(local_src) };
+                    self.line(line,props,&mut next)?;
+                },
+            },
+            _ if self.long.is_some() => next.push_event(local_src.map(|se| se.into())),
+            _ => {
+                self.current.push(local_src);
+                if self.current.len() > props.max_length {
+                    let line = Line { events: std::mem::take(&mut self.current), end: None };
+                    let blank = line.is_blank();
+                    self.decide(blank,props,&mut next)?;
+                    self.blank_before = blank;
+                    self.long = Some(blank);
+                    line.push_into(&mut next);
+                }
+            },
+        }
+        Ok(next.with_state(self))
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    Local, ParserEvent,
+    Parser, Processed,
+    Source, ChunkSource, Feeder,
+    Error,
+};
+
+/*
+
+   Push-based incremental parsing: text pieces are fed as they arrive,
+   events completed so far are collected and can be drained at any time.
+
+   Parser state (an unfinished tag, entity, ...) is suspended at the end of every chunk
+   and resumed with the next one, StateMachine::eof is called only by finish().
+   Offsets are global, as if all the chunks were a single text:
+
+      let mut inc = Incremental::new(tagger::Builder::new().create());
+      inc.feed("<a hr")?;                // no events yet
+      inc.feed("ef=\"x\">link</a>")?;
+      inc.finish()?;
+      for local_event in inc.drain_events() { ... }
+
+   Errors (except Error::Pending) are returned from feed/finish, events collected
+   before the error are kept.
+
+*/
+
+pub struct Incremental<P: Parser> {
+    parser: P,
+    source: ChunkSource,
+    feeder: Feeder,
+    events: Vec<Local<ParserEvent<P::Data>>>,
+}
+impl<P: Parser> Incremental<P> {
+    pub fn new(parser: P) -> Incremental<P> {
+        let (source,feeder) = ChunkSource::new();
+        Incremental {
+            parser,
+            source,
+            feeder,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &str) -> Result<(),Error> {
+        self.feed_bytes(chunk.as_bytes())
+    }
+    // utf-8 bytes, a code point can be split between chunks
+    pub fn feed_bytes(&mut self, chunk: &[u8]) -> Result<(),Error> {
+        self.feeder.push(chunk);
+        self.run()
+    }
+    pub fn finish(&mut self) -> Result<(),Error> {
+        self.feeder.close();
+        self.run()
+    }
+
+    pub fn drain_events(&mut self) -> Vec<Local<ParserEvent<P::Data>>> {
+        std::mem::take(&mut self.events)
+    }
+    pub fn processed(&self) -> Processed {
+        self.source.processed()
+    }
+    pub fn into_inner(self) -> P {
+        self.parser
+    }
+
+    fn run(&mut self) -> Result<(),Error> {
+        loop {
+            match self.parser.next_event(&mut self.source) {
+                Ok(Some(local_pe)) => self.events.push(local_pe),
+                Ok(None) |
+                Err(Error::Pending) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn whole<P: Parser>(mut parser: P, text: &str) -> Vec<Local<ParserEvent<P::Data>>> {
+        let mut src = text.into_source();
+        let mut res = Vec::new();
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            res.push(local_event);
+        }
+        res
+    }
+
+    #[test]
+    fn split_tag() {
+        let text = "Ёж <a href=\"x\">&amp;</a>";
+        let builder = tagger::Builder::new().with_attribute(tagger::TagName::A,"href");
+        let res = whole(builder.clone().create(),text);
+
+        let mut inc = Incremental::new(builder.create());
+        inc.feed("Ёж <a hr").unwrap();
+        let mut events = inc.drain_events();
+        assert_eq!(events.len(),3);
+        inc.feed("ef=\"x\">&am").unwrap();
+        let tag = inc.drain_events();
+        assert_eq!(tag.len(),4); // tag, '&', 'a', 'm'
+        assert_eq!(tag[0].chars(),Snip { offset: 3, length: 12 });
+        assert_eq!(tag[0].bytes(),Snip { offset: 5, length: 12 });
+        events.extend(tag);
+        inc.feed("p;</a").unwrap();
+        inc.feed(">").unwrap();
+        inc.finish().unwrap();
+        events.extend(inc.drain_events());
+        assert_eq!(events,res);
+        assert_eq!(inc.processed(),Processed::of(text));
+    }
+
+    #[test]
+    fn entities_and_eof() {
+        let text = "a&amp;b&#128175;&nbsp";
+        let res = whole(entities::Builder::new().create(),text);
+        for chunk in [1,2,3] {
+            let mut inc = Incremental::new(entities::Builder::new().create());
+            for bytes in text.as_bytes().chunks(chunk) {
+                inc.feed_bytes(bytes).unwrap();
+            }
+            inc.finish().unwrap();
+            assert_eq!(format!("{:?}",inc.drain_events()),format!("{:?}",res));
+        }
+
+        let mut inc = Incremental::new(tagger::Builder::new().create());
+        inc.feed("x <b").unwrap();
+        assert_eq!(inc.drain_events().len(),2);
+        assert!(matches!(inc.finish(),Err(Error::EofInTag { .. })));
+    }
+}
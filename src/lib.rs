@@ -17,11 +17,46 @@ pub use source::{
     SourceExt,
     SourceResult,
     SourceEvent,
+    FlatMapper, Mapped,
+    MergePolicy, Classifier,
+    Shift,
 
     //Pipe, Filtered, IntoSeparator, Chain,
-    //Shift,
 };
 
+mod reader;
+pub use reader::ReaderSource;
+pub use encoding::{BytesSource,Utf8Mode};
+
+mod chunks;
+pub use chunks::{ChunkSource,Feeder};
+
+mod incremental;
+pub use incremental::Incremental;
+
+#[cfg(feature = "async")]
+mod driver;
+#[cfg(feature = "async")]
+pub use driver::{AsyncDriver,AsyncInput,ReadInput,StreamInput};
+
+mod lines;
+pub use lines::{LineIndex,Position};
+
+mod graphemes;
+pub use graphemes::{Graphemes,GraphemeParser,GraphemeCheckpoint,Grapheme};
+
+mod normalize;
+pub use normalize::{Normalize,NormalForm};
+
+mod case_fold;
+pub use case_fold::CaseFold;
+
+mod text;
+pub use text::{TextBuilder,ProcessedText};
+
+mod fields;
+pub use fields::{FieldsSource,FieldLayout,InFields,InField};
+
 pub mod parser;
 pub use parser::{
     Parser,
@@ -36,18 +71,37 @@ pub use parser::{
     // Pipe
 };
 
+mod checkpoint;
+pub use checkpoint::{Checkpoint,Snapshot};
+
+mod diagnostics;
+pub use diagnostics::{Diagnostics,Warning};
+
 mod state;
 pub use state::{
     NextResult, Next,
     StateMachine, Runtime,
+    RuntimeState,
 };
 
+pub mod encoding {
+    mod tables;
+    mod utf8;
+    mod decoder;
+    mod sniff;
+
+    pub use utf8::{BytesSource,Utf8Mode};
+    pub(crate) use utf8::incomplete_utf8;
+    pub use decoder::{Encoding,DecodingSource};
+    pub use sniff::{Sniffer,Detected};
+}
+
 pub mod entities {
     mod entities;
     mod parser;
     mod state;
 
-    pub use parser::{Builder,EntityParser,PipedEntityParser};
+    pub use parser::{Builder,EntityParser,PipedEntityParser,EntityCheckpoint};
 }
 
 pub mod tagger {
@@ -55,7 +109,7 @@ pub mod tagger {
     mod state;
     mod parser;
 
-    pub use parser::{Builder,TagParser};
+    pub use parser::{Builder,TagParser,TagCheckpoint};
     pub use tags::{Tag,TagName,Closing,SpecTag};
 }
 
@@ -63,44 +117,205 @@ pub mod paragraph {
     mod parser;
     mod state;
 
-    pub use parser::{Builder,Paragraphs};
+    pub use parser::{Builder,Paragraphs,ParaCheckpoint};
+}
+
+pub mod sentence {
+    mod abbreviations;
+    mod parser;
+    mod state;
+    mod unicode;
+
+    pub use parser::{Builder,Sentences,SentCheckpoint,Language};
+    pub use state::Sentence;
+}
+
+pub mod words {
+    mod parser;
+    mod state;
+
+    pub use parser::{Builder,Words,WordCheckpoint};
+    pub use state::{Word,WordKind};
+}
+
+pub mod linebreak {
+    mod parser;
+    mod state;
+
+    pub use parser::{Builder,LineBreaks,LineBreakCheckpoint};
+    pub use state::LineBreakOpportunity;
 }
 
+pub mod headings {
+    mod parser;
+    mod state;
+
+    pub use parser::{Builder,Headings,HeadingCheckpoint};
+    pub use state::{Heading,HeadingKind};
+}
+
+
+/*
 
+   Every error (except Pending) carries the Local where it happened:
+
+      EofInTag         end of input inside a tag, Local of the unfinished tag and its raw chars
+      EndBeforeBegin   segment with the end before its begin
+      NoBegin          segment without a begin, Local of its end
+      Io               reading failed, zero-length Local at the processed position
+      InvalidUtf8      invalid utf-8 sequence, bytes of Local are the invalid bytes
+      InvalidInput     input rejected by a parser (custom StateMachines)
+      LimitExceeded    configured limit is exceeded (tagger max tag length, ...)
+      MalformedMarkup  markup that can't be parsed (custom StateMachines)
+      Pending          source has no data yet (not eof), can be polled again later: it is not a failure
+                       and is returned only by sources fed by chunks (ChunkSource, driver, incremental)
+
+*/
 pub enum Error {
-    EofInTag(Vec<Local<SourceEvent>>),
-    EndBeforeBegin,
-    NoBegin,
+    EofInTag { local: Local<()>, raw: Vec<Local<SourceEvent>> },
+    EndBeforeBegin { begin: Local<()>, end: Local<()> },
+    NoBegin { local: Local<()> },
+    Io { local: Local<()>, error: std::io::Error },
+    InvalidUtf8 { local: Local<()>, error: std::str::Utf8Error },
+    InvalidInput { local: Local<()>, message: String },
+    LimitExceeded { local: Local<()>, limit: usize },
+    MalformedMarkup { local: Local<()>, message: String },
+    Pending,
+}
+
+impl Error {
+    pub(crate) fn eof_in_tag(raw: Vec<Local<SourceEvent>>) -> Error {
+        let local = match (raw.first(),raw.last()) {
+            (Some(first),Some(last)) => Local::from_segment(first.local(()),*last).unwrap_or(first.local(())),
+            _ => ().localize(Snip { offset: 0, length: 0 },Snip { offset: 0, length: 0 }),
+        };
+        Error::EofInTag { local, raw }
+    }
+
+    // no data yet, next_char/next_event can be retried
+    pub fn is_pending(&self) -> bool {
+        matches!(self,Error::Pending)
+    }
+
+    pub fn local(&self) -> Option<Local<()>> {
+        match self {
+            Error::EofInTag { local, .. } |
+            Error::NoBegin { local } |
+            Error::Io { local, .. } |
+            Error::InvalidUtf8 { local, .. } |
+            Error::InvalidInput { local, .. } |
+            Error::LimitExceeded { local, .. } |
+            Error::MalformedMarkup { local, .. } => Some(*local),
+            Error::EndBeforeBegin { end, .. } => Some(*end),
+            Error::Pending => None,
+        }
+    }
+
+    pub(crate) fn with_shift(self, shift: Processed) -> Error {
+        match self {
+            Error::EofInTag { local, raw } => Error::EofInTag {
+                local: local.with_offset(shift),
+                raw: raw.into_iter().map(|lse| lse.with_offset(shift)).collect(),
+            },
+            Error::EndBeforeBegin { begin, end } => Error::EndBeforeBegin { begin: begin.with_offset(shift), end: end.with_offset(shift) },
+            Error::NoBegin { local } => Error::NoBegin { local: local.with_offset(shift) },
+            Error::Io { local, error } => Error::Io { local: local.with_offset(shift), error },
+            Error::InvalidUtf8 { local, error } => Error::InvalidUtf8 { local: local.with_offset(shift), error },
+            Error::InvalidInput { local, message } => Error::InvalidInput { local: local.with_offset(shift), message },
+            Error::LimitExceeded { local, limit } => Error::LimitExceeded { local: local.with_offset(shift), limit },
+            Error::MalformedMarkup { local, message } => Error::MalformedMarkup { local: local.with_offset(shift), message },
+            Error::Pending => Error::Pending,
+        }
+    }
 }
 
 impl std::fmt::Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::EndBeforeBegin => f.debug_struct("EndBeforeBegin"),
-            Error::NoBegin => f.debug_struct("NoBegin"),
-            Error::EofInTag(v) => {
-                let mut dbg = f.debug_struct("EofInTag");
-                let mut iter = v.into_iter();
-                if let Some(lse) = iter.next() {
-                    let (local,se) = lse.into_inner();
-                    let first = local;
-                    let mut last = local;
-                    let mut s = String::new();
-                    push_s(se,&mut s);
-                    for lse in iter {
-                        let (local,se) = lse.into_inner();
-                        last = local;
-                        push_s(se,&mut s);
-                    }                    
-                    if let Ok(lc) = Local::from_segment(first,last) {
-                        dbg.field("chars", &lc.chars())
-                            .field("bytes", &lc.bytes());
-                    }
-                    dbg.field("data", &s);                    
+            Error::EndBeforeBegin { begin, end } => f.debug_struct("EndBeforeBegin")
+                .field("begin", begin)
+                .field("end", end)
+                .finish(),
+            Error::NoBegin { local } => f.debug_struct("NoBegin")
+                .field("local", local)
+                .finish(),
+            Error::Pending => f.debug_struct("Pending").finish(),
+            Error::Io { local, error } => f.debug_struct("Io")
+                .field("local", local)
+                .field("error", error)
+                .finish(),
+            Error::InvalidUtf8 { local, error } => f.debug_struct("InvalidUtf8")
+                .field("local", local)
+                .field("error", error)
+                .finish(),
+            Error::InvalidInput { local, message } => f.debug_struct("InvalidInput")
+                .field("local", local)
+                .field("message", message)
+                .finish(),
+            Error::LimitExceeded { local, limit } => f.debug_struct("LimitExceeded")
+                .field("local", local)
+                .field("limit", limit)
+                .finish(),
+            Error::MalformedMarkup { local, message } => f.debug_struct("MalformedMarkup")
+                .field("local", local)
+                .field("message", message)
+                .finish(),
+            Error::EofInTag { local, raw } => {
+                let mut s = String::new();
+                for lse in raw {
+                    push_s(*lse.data(),&mut s);
+                }
+                f.debug_struct("EofInTag")
+                    .field("chars", &local.chars())
+                    .field("bytes", &local.bytes())
+                    .field("data", &s)
+                    .finish()
+            },
+        }
+    }
+}
+
+// "chars 3..8"
+struct Span(Local<()>);
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Snip { offset, length } = self.0.chars();
+        write!(f, "chars {}..{}", offset, offset + length)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::EofInTag { local, raw } => {
+                let mut s = String::new();
+                for lse in raw {
+                    push_s(*lse.data(),&mut s);
                 }
-                dbg
-            }
-        }.finish()
+                write!(f, "unexpected end of input in tag {:?} at {}", s, Span(*local))
+            },
+            Error::EndBeforeBegin { begin, end } => write!(f, "segment end at {} is before its begin at {}", Span(*end), Span(*begin)),
+            Error::NoBegin { local } => write!(f, "segment without begin at {}", Span(*local)),
+            Error::Io { local, error } => write!(f, "i/o error at {}: {}", Span(*local), error),
+            Error::InvalidUtf8 { local, .. } => {
+                let Snip { offset, length } = local.bytes();
+                write!(f, "invalid utf-8 at bytes {}..{}", offset, offset + length)
+            },
+            Error::InvalidInput { local, message } => write!(f, "invalid input at {}: {}", Span(*local), message),
+            Error::LimitExceeded { local, limit } => write!(f, "limit of {} exceeded at {}", limit, Span(*local)),
+            Error::MalformedMarkup { local, message } => write!(f, "malformed markup at {}: {}", Span(*local), message),
+            Error::Pending => write!(f, "no input data yet"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { error, .. } => Some(error),
+            Error::InvalidUtf8 { error, .. } => Some(error),
+            _ => None,
+        }
     }
 }
 
@@ -111,3 +326,38 @@ fn push_s(se: SourceEvent, s: &mut String) {
         SourceEvent::Breaker(_) => s.push(' '),
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_error<S: Source>(mut src: S) -> Result<(),Box<dyn std::error::Error + Send + Sync>> {
+        while src.next_char()?.is_some() {}
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        let src = "ab <a href=\"x\">".into_source()
+            .pipe(tagger::Builder::new().with_max_tag_length(5).create().into_breaker());
+        let err = first_error(src).unwrap_err();
+        assert_eq!(err.to_string(),"limit of 5 exceeded at chars 3..9");
+        match err.downcast_ref::<Error>() {
+            Some(Error::LimitExceeded { local, limit: 5 }) => assert_eq!(local.bytes(),Snip { offset: 3, length: 6 }),
+            e => panic!("unexpected error: {:?}",e),
+        }
+
+        let src = BytesSource::strict(b"ok\xFF")
+            .with_offset(Processed { chars: 10, bytes: 12, utf16: 10 });
+        let err = first_error(src).unwrap_err();
+        assert_eq!(err.to_string(),"invalid utf-8 at bytes 14..15");
+        assert!(err.source().is_some());
+        let err = err.downcast::<Error>().unwrap();
+        assert_eq!(err.local().unwrap().chars(),Snip { offset: 12, length: 0 });
+
+        let err = first_error("x\n<b".into_source().pipe(tagger::Builder::new().create().into_breaker())).unwrap_err();
+        assert_eq!(err.to_string(),"unexpected end of input in tag \"<b\" at chars 2..4");
+        assert!(!err.downcast::<Error>().unwrap().is_pending());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{
+    ParserResult,
+    Source,
+    Parser, Runtime,
+    RuntimeState, Checkpoint,
+};
+
+use super::{
+    state::{LineBreakState,LineBreakOpportunity},
+};
+
+/*
+
+   Line breaking (UAX #14): https://www.unicode.org/reports/tr14/
+
+   Events are passed as they are, a break opportunity is a zero-length ParserEvent::Parsed(LineBreakOpportunity)
+   at the beginning of the event it precedes ("a b" => 'a', ' ', Allowed, 'b').
+
+   Breakers take part as chars: None as a zero width space (<wbr>), Space, Word and Sentence as a space,
+   Line, Paragraph and Section as a line feed, so there is a mandatory break after them.
+
+*/
+
+#[derive(Debug,Clone,Default)]
+pub struct Builder {
+
+}
+impl Builder {
+    pub fn new() -> Builder {
+        Builder{
+
+        }
+    }
+    pub fn create(self) -> LineBreaks {
+        LineBreaks(Runtime::new(()))
+    }
+}
+
+
+pub struct LineBreaks(Runtime<LineBreakState,LineBreakOpportunity,()>);
+
+impl Parser for LineBreaks {
+    type Data = LineBreakOpportunity;
+
+    fn next_event<S: Source>(&mut self, src: &mut S) -> ParserResult<LineBreakOpportunity> {
+        self.0.next_event(src)
+    }
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct LineBreakCheckpoint(RuntimeState<LineBreakState,LineBreakOpportunity>);
+
+impl Checkpoint for LineBreaks {
+    type State = LineBreakCheckpoint;
+
+    fn checkpoint(&self) -> Option<LineBreakCheckpoint> {
+        self.0.checkpoint().map(LineBreakCheckpoint)
+    }
+    fn restore(&mut self, state: LineBreakCheckpoint) {
+        self.0.restore(state.0);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use super::*;
+
+    // text with '|' for allowed and '!' for mandatory breaks
+    fn annotated<S: Source>(mut src: S) -> String {
+        let mut parser = Builder::new().create();
+        let mut res = String::new();
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            match local_event.data() {
+                ParserEvent::Char(c) => res.push(*c),
+                ParserEvent::Breaker(Breaker::None) => {},
+                ParserEvent::Breaker(Breaker::Line) => res.push('\n'),
+                ParserEvent::Breaker(_) => res.push(' '),
+                ParserEvent::Parsed(LineBreakOpportunity::Allowed) => res.push('|'),
+                ParserEvent::Parsed(LineBreakOpportunity::Mandatory) => res.push('!'),
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn basic() {
+        let mut src = "a b\nc".into_source();
+        let mut parser = Builder::new().create();
+
+        let mut res_iter = [
+            ParserEvent::Char('a').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            ParserEvent::Char(' ').localize(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 1 }),
+            ParserEvent::Parsed(LineBreakOpportunity::Allowed).localize(Snip { offset: 2, length: 0 },Snip { offset: 2, length: 0 }),
+            ParserEvent::Char('b').localize(Snip { offset: 2, length: 1 },Snip { offset: 2, length: 1 }),
+            ParserEvent::Char('\n').localize(Snip { offset: 3, length: 1 },Snip { offset: 3, length: 1 }),
+            ParserEvent::Parsed(LineBreakOpportunity::Mandatory).localize(Snip { offset: 4, length: 0 },Snip { offset: 4, length: 0 }),
+            ParserEvent::Char('c').localize(Snip { offset: 4, length: 1 },Snip { offset: 4, length: 1 }),
+        ].into_iter();
+
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Parser: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn rules() {
+        assert_eq!(annotated("Hello, world! (e.g. 3.14) well-known «цитата»".into_source()),
+                   "Hello, |world! |(e.g. |3.14) |well-|known |«цитата»");
+        assert_eq!(annotated("日本語です。".into_source()),"日|本|語|で|す。");
+    }
+
+    #[test]
+    fn breakers() {
+        let src = "<p>long<wbr>word<br>next</p>".into_source()
+            .pipe(tagger::Builder::new().create().into_breaker());
+        assert_eq!(annotated(src)," !long|word\n!next ");
+    }
+}
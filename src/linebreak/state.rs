@@ -0,0 +1,67 @@
+use unicode_linebreak::{linebreaks, split_at_safe, BreakOpportunity};
+
+use crate::{
+    Local, ParserEvent,
+    NextResult, Next, StateMachine,
+    SourceEvent, Breaker,
+};
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum LineBreakOpportunity {
+    Mandatory,
+    Allowed,
+}
+
+/*
+
+   The break before a char depends on the preceding chars only, so every event is passed at once,
+   preceded by the break opportunity (if any). Preceding chars are kept only back to the point where
+   further breaks stop depending on them (unicode_linebreak::split_at_safe).
+
+*/
+
+#[derive(Debug,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub(in super) struct LineBreakState {
+    context: String,
+}
+
+fn as_char(se: &SourceEvent) -> char {
+    match se {
+        SourceEvent::Char(c) => *c,
+        SourceEvent::Breaker(Breaker::None) => '\u{200B}',
+        SourceEvent::Breaker(Breaker::Space) |
+        SourceEvent::Breaker(Breaker::Word) |
+        SourceEvent::Breaker(Breaker::Sentence) => ' ',
+        SourceEvent::Breaker(Breaker::Line) |
+        SourceEvent::Breaker(Breaker::Paragraph) |
+        SourceEvent::Breaker(Breaker::Section) => '\n',
+    }
+}
+
+impl StateMachine for LineBreakState {
+    type Context = ();
+    type Data = LineBreakOpportunity;
+
+    // no opportunity at the end of text
+    fn eof(self, _props: &()) -> NextResult<LineBreakState,LineBreakOpportunity> {
+        Ok(Next::empty())
+    }
+    fn next_state(mut self, local_src: Local<SourceEvent>, _props: &()) -> NextResult<LineBreakState,LineBreakOpportunity> {
+        let position = self.context.len();
+        self.context.push(as_char(local_src.data()));
+        let mut next = Next::empty();
+        if let Some((_,opportunity)) = linebreaks(&self.context).find(|(i,_)| *i == position) {
+            next.push_event(local_src.local(ParserEvent::Parsed(match opportunity {
+                BreakOpportunity::Mandatory => LineBreakOpportunity::Mandatory,
+                BreakOpportunity::Allowed => LineBreakOpportunity::Allowed,
+            })).into_position());
+        }
+        next.push_event(local_src.map(|se| se.into()));
+        let (prev,_) = split_at_safe(&self.context);
+        let safe = prev.len();
+        self.context.drain(.. safe);
+        Ok(next.with_state(self))
+    }
+}
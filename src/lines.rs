@@ -0,0 +1,186 @@
+use crate::{
+    Local, Snip,
+    Source, SourceEvent, IntoSource,
+    Error,
+};
+
+/*
+
+   Line/column index: line starts (in chars and in bytes) of the original text.
+
+   Built from a raw source (StrSource, ReaderSource, BytesSource, Chain of them ...) before any parser
+   is piped, so offsets of every Local produced later by the pipeline can be turned into line:column.
+   Chain shifts offsets of the chained source, so the index built from a Chain (or pushed from several
+   sources in the same order) stays consistent with Locals of the whole pipeline.
+
+   Line breaks: "\n", "\r\n", "\r". Lines and columns are 1-based, columns are counted in chars.
+
+*/
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq,Ord,PartialOrd)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,"line {}, col {}",self.line,self.column)
+    }
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct LineIndex {
+    // (chars, bytes) offsets of line starts, the first one is always (0,0)
+    starts: Vec<(usize,usize)>,
+    cr: bool,
+}
+impl Default for LineIndex {
+    fn default() -> LineIndex {
+        LineIndex::new()
+    }
+}
+impl LineIndex {
+    pub fn new() -> LineIndex {
+        LineIndex {
+            starts: vec![(0,0)],
+            cr: false,
+        }
+    }
+    pub fn from_text<I: IntoSource>(text: I) -> Result<LineIndex,Error> {
+        LineIndex::from_source(text.into_source())
+    }
+    pub fn from_source<S: Source>(mut src: S) -> Result<LineIndex,Error> {
+        let mut index = LineIndex::new();
+        while let Some(local_se) = src.next_char()? {
+            index.push(&local_se);
+        }
+        Ok(index)
+    }
+
+    pub fn push(&mut self, local_se: &Local<SourceEvent>) {
+        let next = (local_se.chars().offset + local_se.chars().length, local_se.bytes().offset + local_se.bytes().length);
+        match local_se.data() {
+            SourceEvent::Char('\n') if self.cr => {
+                // "\r\n" is a single line break
+                if let Some(last) = self.starts.last_mut() {
+                    *last = next;
+                }
+                self.cr = false;
+            },
+            SourceEvent::Char('\n') => {
+                self.starts.push(next);
+            },
+            SourceEvent::Char('\r') => {
+                self.starts.push(next);
+                self.cr = true;
+            },
+            _ => self.cr = false,
+        }
+    }
+
+    pub fn lines(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn position(&self, char_offset: usize) -> Position {
+        let idx = match self.starts.binary_search_by(|(c,_)| c.cmp(&char_offset)) {
+            Ok(idx) => idx,
+            Err(idx) => idx - 1,
+        };
+        Position {
+            line: idx + 1,
+            column: char_offset - self.starts[idx].0 + 1,
+        }
+    }
+    pub fn line_of_byte(&self, byte_offset: usize) -> usize {
+        match self.starts.binary_search_by(|(_,b)| b.cmp(&byte_offset)) {
+            Ok(idx) => idx + 1,
+            Err(idx) => idx,
+        }
+    }
+
+    pub fn begin<E>(&self, local: &Local<E>) -> Position {
+        self.position(local.chars().offset)
+    }
+    // position of the last char of the local
+    pub fn end<E>(&self, local: &Local<E>) -> Position {
+        let Snip { offset, length } = local.chars();
+        self.position(offset + length.max(1) - 1)
+    }
+}
+
+impl Error {
+    pub fn position(&self, index: &LineIndex) -> Option<Position> {
+        self.local().map(|local| index.begin(&local))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn positions() {
+        let text = "Привет\nмир\r\n\r\nthird\rlast";
+        let index = LineIndex::from_text(text).unwrap();
+        assert_eq!(index.lines(),5);
+
+        let res = [
+            (0,Position { line: 1, column: 1 }),
+            (6,Position { line: 1, column: 7 }),
+            (7,Position { line: 2, column: 1 }),
+            (10,Position { line: 2, column: 4 }),
+            (11,Position { line: 2, column: 5 }),
+            (12,Position { line: 3, column: 1 }),
+            (13,Position { line: 3, column: 2 }),
+            (14,Position { line: 4, column: 1 }),
+            (20,Position { line: 5, column: 1 }),
+            (23,Position { line: 5, column: 4 }),
+        ];
+        for (offset,pos) in res {
+            assert_eq!(index.position(offset),pos);
+        }
+
+        assert_eq!(index.line_of_byte(0),1);
+        assert_eq!(index.line_of_byte(12),1);
+        assert_eq!(index.line_of_byte(13),2);
+        assert_eq!(index.line_of_byte(19),2);
+        assert_eq!(index.line_of_byte(23),4);
+    }
+
+    #[test]
+    fn chain_and_error() {
+        let index = LineIndex::from_source("<p>first\n".into_source().chain("<p\nclass=\"x".into_source())).unwrap();
+        assert_eq!(index.lines(),3);
+
+        let mut src = "<p>first\n".into_source()
+            .chain("<p\nclass=\"x".into_source())
+            .pipe(tagger::Builder::new().create().into_breaker());
+        let err = loop {
+            match src.next_char() {
+                Ok(Some(_)) => continue,
+                Ok(None) => panic!("error expected"),
+                Err(e) => break e,
+            }
+        };
+        let pos = err.position(&index).unwrap();
+        assert_eq!(pos,Position { line: 2, column: 1 });
+        assert_eq!(format!("{}",pos),"line 2, col 1");
+        assert_eq!(index.end(&err.local().unwrap()),Position { line: 3, column: 8 });
+    }
+
+    #[test]
+    fn eof_in_tag() {
+        let mut src = "x\n<b".into_source().pipe(tagger::Builder::new().create().into_breaker());
+        let err = loop {
+            match src.next_char() {
+                Ok(Some(_)) => continue,
+                Ok(None) => panic!("error expected"),
+                Err(e) => break e,
+            }
+        };
+        let index = LineIndex::from_text("x\n<b").unwrap();
+        assert_eq!(err.position(&index),Some(Position { line: 2, column: 1 }));
+    }
+}
@@ -1,16 +1,36 @@
-use crate::Error;
+use crate::{
+    Error,
+    source::Processed,
+};
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Snip {
     pub offset: usize,
     pub length: usize,
 }
 
+/*
+
+   localize() sets utf16 = chars, it is valid only for text without astral (non-BMP) chars: '💯' is 1 char,
+   but 2 utf16 code units. Sources of arbitrary text have to track utf16 and use localize_utf16().
+
+*/
 pub trait Localize: Sized {
+    // utf16 == chars (no astral chars)
     fn localize(self, chars: Snip, bytes: Snip) -> Local<Self> {
         Local {
             chars,
             bytes,
+            utf16: chars,
+            data: self,
+        }
+    }
+    fn localize_utf16(self, chars: Snip, bytes: Snip, utf16: Snip) -> Local<Self> {
+        Local {
+            chars,
+            bytes,
+            utf16,
             data: self,
         }
     }
@@ -18,9 +38,11 @@ pub trait Localize: Sized {
 impl<T: Sized> Localize for T {}
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Local<E> {
     chars: Snip,
     bytes: Snip,
+    utf16: Snip,
     data: E,
 }
 impl<E> Local<E> {
@@ -29,6 +51,7 @@ impl<E> Local<E> {
             Local {
                 chars: self.chars,
                 bytes: self.bytes,
+                utf16: self.utf16,
                 data: (),
             },
             self.data,
@@ -43,10 +66,15 @@ impl<E> Local<E> {
     pub fn bytes(&self) -> Snip {
         self.bytes
     }
+    // offsets in UTF-16 code units (JavaScript, LSP)
+    pub fn utf16(&self) -> Snip {
+        self.utf16
+    }
 
     pub fn into_position(mut self) -> Local<E> {
         self.chars.length = 0;
         self.bytes.length = 0;
+        self.utf16.length = 0;
         self
     }
 
@@ -54,6 +82,7 @@ impl<E> Local<E> {
         Local {
             chars: self.chars,
             bytes: self.bytes,
+            utf16: self.utf16,
             data,
         }
     }
@@ -64,6 +93,7 @@ impl<E> Local<E> {
         Local {
             chars: self.chars,
             bytes: self.bytes,
+            utf16: self.utf16,
             data: mapper(self.data),
         }
     }
@@ -71,17 +101,23 @@ impl<E> Local<E> {
         Local {
             chars: self.chars,
             bytes: self.bytes,
+            utf16: self.utf16,
             data: inner,
         }
     }
-    pub fn with_shift(mut self, char_offset: usize, byte_offset: usize) -> Local<E> {
-        self.chars.offset += char_offset;
-        self.bytes.offset += byte_offset;
+    #[deprecated(since = "0.6.7", note = "use with_offset(Processed), utf16 is shifted by chars here")]
+    pub fn with_shift(self, char_offset: usize, byte_offset: usize) -> Local<E> {
+        self.with_offset(Processed { chars: char_offset, bytes: byte_offset, utf16: char_offset })
+    }
+    pub fn with_offset(mut self, shift: Processed) -> Local<E> {
+        self.chars.offset += shift.chars;
+        self.bytes.offset += shift.bytes;
+        self.utf16.offset += shift.utf16;
         self
     }
 
     pub fn from_segment<T>(begin: Local<E>, end: Local<T>) -> Result<Local<E>, Error> {
-        if (begin.chars.offset <= end.chars.offset) && (begin.bytes.offset <= end.bytes.offset) {
+        if (begin.chars.offset <= end.chars.offset) && (begin.bytes.offset <= end.bytes.offset) && (begin.utf16.offset <= end.utf16.offset) {
             Ok(Local {
                 chars: Snip {
                     offset: begin.chars.offset,
@@ -91,10 +127,14 @@ impl<E> Local<E> {
                     offset: begin.bytes.offset,
                     length: end.bytes.length + end.bytes.offset - begin.bytes.offset,
                 },
+                utf16: Snip {
+                    offset: begin.utf16.offset,
+                    length: end.utf16.length + end.utf16.offset - begin.utf16.offset,
+                },
                 data: begin.data,
             })
         } else {
-            Err(Error::EndBeforeBegin)
+            Err(Error::EndBeforeBegin { begin: begin.local(()), end: end.local(()) })
         }
     }
 }
@@ -0,0 +1,295 @@
+use std::collections::VecDeque;
+
+use unicode_normalization::char::{
+    decompose_canonical, decompose_compatible,
+    canonical_combining_class, compose,
+};
+
+use crate::{
+    Local,
+    Source, SourceResult, SourceEvent, Processed,
+    Error, Checkpoint,
+};
+
+/*
+
+   Unicode normalization: https://www.unicode.org/reports/tr15/
+
+   Decomposed chars share the Local of the char they came from,
+   composed char gets the merged Local of all the chars it was composed of:
+
+      "e&#x301;" (entities) --NFC--> 'é' with Local of the whole "e&#x301;"
+      "ﬁ"                   --NFKD-> 'f','i' both with Local of "ﬁ"
+
+   Marks reordered by combining class (canonical ordering) all get the merged Local of the marks,
+   so offsets never go backwards:
+
+      "a&#x301;&#x316;"     --NFD--> 'a', '\u{316}','\u{301}' both with Local of "&#x301;&#x316;"
+
+   Chars are buffered until the next starter (ccc = 0), breakers flush the buffer
+   and are never composed with chars.
+
+*/
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+pub enum NormalForm {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+impl NormalForm {
+    fn compatible(&self) -> bool {
+        matches!(self,NormalForm::Nfkc | NormalForm::Nfkd)
+    }
+    fn composed(&self) -> bool {
+        matches!(self,NormalForm::Nfc | NormalForm::Nfkc)
+    }
+}
+
+pub struct Normalize<S> {
+    source: S,
+    form: NormalForm,
+    buffer: Vec<(char,Local<()>)>,
+    ready: VecDeque<Local<SourceEvent>>,
+    eof: bool,
+}
+impl<S: Source> Normalize<S> {
+    pub(crate) fn new(source: S, form: NormalForm) -> Normalize<S> {
+        Normalize {
+            source,
+            form,
+            buffer: Vec::new(),
+            ready: VecDeque::new(),
+            eof: false,
+        }
+    }
+
+    fn push(&mut self, c: char, local: Local<()>) -> Result<(),Error> {
+        let mut decomposed = Vec::new();
+        match self.form.compatible() {
+            true => decompose_compatible(c,|d| decomposed.push(d)),
+            false => decompose_canonical(c,|d| decomposed.push(d)),
+        }
+        for d in decomposed {
+            if (canonical_combining_class(d) == 0) && !self.buffer.is_empty() {
+                reorder(&mut self.buffer)?;
+                if self.form.composed() {
+                    self.buffer.push((d,local));
+                    self.buffer = recompose(std::mem::take(&mut self.buffer))?;
+                    // the last starter (with its non-starters) can be composed with the following chars
+                    let last = self.buffer.iter().rposition(|(c,_)| canonical_combining_class(*c) == 0).unwrap_or(0);
+                    let tail = self.buffer.split_off(last);
+                    self.release();
+                    self.buffer = tail;
+                    continue;
+                }
+                self.release();
+            }
+            self.buffer.push((d,local));
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(),Error> {
+        reorder(&mut self.buffer)?;
+        if self.form.composed() {
+            self.buffer = recompose(std::mem::take(&mut self.buffer))?;
+        }
+        self.release();
+        Ok(())
+    }
+
+    fn release(&mut self) {
+        for (c,local) in self.buffer.drain(..) {
+            self.ready.push_back(local.local(SourceEvent::Char(c)));
+        }
+    }
+}
+
+// canonical ordering: stable sort of every run of non-starters by ccc,
+// marks of a reordered run share the local of the whole run (offsets don't go backwards)
+fn reorder(buffer: &mut [(char,Local<()>)]) -> Result<(),Error> {
+    for run in buffer.split_mut(|(c,_)| canonical_combining_class(*c) == 0) {
+        if !run.is_sorted_by_key(|(c,_)| canonical_combining_class(*c)) {
+            let mut local = run[0].1;
+            for (_,l) in run.iter().skip(1) {
+                local = merge(local,*l)?;
+            }
+            run.sort_by_key(|(c,_)| canonical_combining_class(*c));
+            for (_,l) in run.iter_mut() {
+                *l = local;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn merge(a: Local<()>, b: Local<()>) -> Result<Local<()>,Error> {
+    let (begin,end) = match a.chars().offset <= b.chars().offset {
+        true => (a,b),
+        false => (b,a),
+    };
+    // end can be inside begin (decomposed chars of the same source char)
+    let end = match (end.chars().offset + end.chars().length) < (begin.chars().offset + begin.chars().length) {
+        true => begin,
+        false => end,
+    };
+    Local::from_segment(begin,end)
+}
+
+// canonical composition of the reordered buffer
+fn recompose(buffer: Vec<(char,Local<()>)>) -> Result<Vec<(char,Local<()>)>,Error> {
+    let mut res: Vec<(char,Local<()>)> = Vec::with_capacity(buffer.len());
+    let mut starter = None;
+    let mut last_ccc = None;
+    for (c,local) in buffer {
+        let ccc = canonical_combining_class(c);
+        if let Some(idx) = starter {
+            let blocked = match last_ccc {
+                Some(last) => (last == 0) || (last >= ccc),
+                None => false,
+            };
+            if !blocked {
+                let (s,slocal): (char,Local<()>) = res[idx];
+                if let Some(composed) = compose(s,c) {
+                    res[idx] = (composed,merge(slocal,local)?);
+                    continue;
+                }
+            }
+        }
+        match ccc {
+            0 => {
+                starter = Some(res.len());
+                last_ccc = None;
+            },
+            _ => last_ccc = Some(ccc),
+        }
+        res.push((c,local));
+    }
+    Ok(res)
+}
+
+impl<S: Source> Source for Normalize<S> {
+    fn next_char(&mut self) -> SourceResult {
+        loop {
+            if let Some(local_se) = self.ready.pop_front() {
+                return Ok(Some(local_se));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+            match self.source.next_char()? {
+                Some(local_se) => {
+                    let (local,se) = local_se.into_inner();
+                    match se {
+                        SourceEvent::Char(c) => self.push(c,local)?,
+                        SourceEvent::Breaker(b) => {
+                            self.flush()?;
+                            self.ready.push_back(local.local(SourceEvent::Breaker(b)));
+                        },
+                    }
+                },
+                None => {
+                    self.flush()?;
+                    self.eof = true;
+                },
+            }
+        }
+    }
+    fn processed(&self) -> Processed {
+        self.source.processed()
+    }
+}
+impl<S> Checkpoint for Normalize<S>
+where S: Checkpoint
+{
+    type State = (S::State,Vec<(char,Local<()>)>,VecDeque<Local<SourceEvent>>,bool);
+
+    fn checkpoint(&self) -> Option<Self::State> {
+        Some((self.source.checkpoint()?,self.buffer.clone(),self.ready.clone(),self.eof))
+    }
+    fn restore(&mut self, (source,buffer,ready,eof): Self::State) {
+        self.source.restore(source);
+        self.buffer = buffer;
+        self.ready = ready;
+        self.eof = eof;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn check<S: Source>(mut src: S, result: Vec<Local<SourceEvent>>) {
+        let mut res_iter = result.into_iter();
+        while let Some(local_event) = src.next_char().unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Source: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+    }
+
+    #[test]
+    fn nfc_entities() {
+        let src = "e&#x301;x \u{1100}\u{1161}\u{11A8}"
+            .into_source()
+            .pipe(crate::entities::Builder::new().create().into_piped())
+            .into_separator()
+            .normalize(NormalForm::Nfc);
+
+        check(src,vec![
+            SourceEvent::Char('é').localize(Snip { offset: 0, length: 8 },Snip { offset: 0, length: 8 }),
+            SourceEvent::Char('x').localize(Snip { offset: 8, length: 1 },Snip { offset: 8, length: 1 }),
+            SourceEvent::Breaker(Breaker::Space).localize(Snip { offset: 9, length: 1 },Snip { offset: 9, length: 1 }),
+            SourceEvent::Char('각').localize(Snip { offset: 10, length: 3 },Snip { offset: 10, length: 9 }),
+        ]);
+    }
+
+    #[test]
+    fn nfd_reorder() {
+        let src = "éa\u{301}\u{316} e"
+            .into_source()
+            .into_separator()
+            .normalize(NormalForm::Nfd);
+
+        check(src,vec![
+            SourceEvent::Char('e').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 2 }),
+            SourceEvent::Char('\u{301}').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 2 }),
+            SourceEvent::Char('a').localize(Snip { offset: 1, length: 1 },Snip { offset: 2, length: 1 }),
+            SourceEvent::Char('\u{316}').localize(Snip { offset: 2, length: 2 },Snip { offset: 3, length: 4 }),
+            SourceEvent::Char('\u{301}').localize(Snip { offset: 2, length: 2 },Snip { offset: 3, length: 4 }),
+            SourceEvent::Breaker(Breaker::Space).localize(Snip { offset: 4, length: 1 },Snip { offset: 7, length: 1 }),
+            SourceEvent::Char('e').localize(Snip { offset: 5, length: 1 },Snip { offset: 8, length: 1 }),
+        ]);
+    }
+
+    #[test]
+    fn compatibility() {
+        let src = "ﬁ①é".into_source().normalize(NormalForm::Nfkd);
+        check(src,vec![
+            SourceEvent::Char('f').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 3 }),
+            SourceEvent::Char('i').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 3 }),
+            SourceEvent::Char('1').localize(Snip { offset: 1, length: 1 },Snip { offset: 3, length: 3 }),
+            SourceEvent::Char('e').localize(Snip { offset: 2, length: 1 },Snip { offset: 6, length: 2 }),
+            SourceEvent::Char('\u{301}').localize(Snip { offset: 2, length: 1 },Snip { offset: 6, length: 2 }),
+        ]);
+
+        let src = "ﬁ①e\u{301}".into_source().normalize(NormalForm::Nfkc);
+        check(src,vec![
+            SourceEvent::Char('f').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 3 }),
+            SourceEvent::Char('i').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 3 }),
+            SourceEvent::Char('1').localize(Snip { offset: 1, length: 1 },Snip { offset: 3, length: 3 }),
+            SourceEvent::Char('é').localize(Snip { offset: 2, length: 2 },Snip { offset: 6, length: 3 }),
+        ]);
+    }
+}
@@ -5,6 +5,8 @@ use crate::{
     Parser, Runtime, Breaker,
     SourceEvent,ParserEvent,
     PipeParser, SourceResult,
+    RuntimeState, Checkpoint,
+    Diagnostics,
 };
 
 use super::{
@@ -20,21 +22,26 @@ use super::{
 
 #[derive(Debug,Clone)]
 pub struct Builder {
-
+    diagnostics: Option<Diagnostics>,
 }
 impl Builder {
     pub fn new() -> Builder {
         Builder{
-          
+            diagnostics: None,
         }
     }
+    // control chars (except TAB, LF, FF, CR) are reported into the sink
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Builder {
+        self.diagnostics = Some(diagnostics);
+        self
+    }
     pub fn create(self) -> Paragraphs {
-        Paragraphs(Runtime::new(()))
+        Paragraphs(Runtime::new(self.diagnostics))
     }
 }
 
 
-pub struct Paragraphs(Runtime<ParaState,Paragraph,()>);
+pub struct Paragraphs(Runtime<ParaState,Paragraph,Option<Diagnostics>>);
 
 impl Parser for Paragraphs {
     type Data = Paragraph;
@@ -44,6 +51,21 @@ impl Parser for Paragraphs {
     }
 }
 
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct ParaCheckpoint(RuntimeState<ParaState,Paragraph>);
+
+impl Checkpoint for Paragraphs {
+    type State = ParaCheckpoint;
+
+    fn checkpoint(&self) -> Option<ParaCheckpoint> {
+        self.0.checkpoint().map(ParaCheckpoint)
+    }
+    fn restore(&mut self, state: ParaCheckpoint) {
+        self.0.restore(state.0);
+    }
+}
+
 impl PipeParser for Paragraphs {
     fn next_char<S: Source>(&mut self, src: &mut S) -> SourceResult {
         Ok(match self.next_event(src)? {
@@ -6,15 +6,19 @@ use crate::{
     Error, Local, ParserEvent,
     NextResult, Next, StateMachine,
     SourceEvent, Breaker,
+    Diagnostics, Warning,
+    diagnostics::warn,
 };
 
-#[derive(Debug,PartialEq)]
+#[derive(Debug,Clone,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Paragraph;
 
-#[derive(Debug)]
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub(in super) enum ParaState {
     Init,
-    First(OptVec<Local<ParserEvent<Paragraph>>>),
+    First(#[cfg_attr(feature = "serde", serde(with = "crate::checkpoint::opt_vec"))] OptVec<Local<ParserEvent<Paragraph>>>),
 }
 impl Default for ParaState {
     fn default() -> ParaState {
@@ -23,10 +27,10 @@ impl Default for ParaState {
 }
 
 impl StateMachine for ParaState {
-    type Context = ();
+    type Context = Option<Diagnostics>;
     type Data = Paragraph;
     
-    fn eof(self, _props: &()) -> NextResult<ParaState,Paragraph> {
+    fn eof(self, _diagnostics: &Option<Diagnostics>) -> NextResult<ParaState,Paragraph> {
         // unexpected EOF
         Ok(match self {
             ParaState::Init => Next::empty(),
@@ -39,7 +43,14 @@ impl StateMachine for ParaState {
             },
         })
     }
-    fn next_state(self, local_src: Local<SourceEvent>, _props: &()) -> NextResult<ParaState,Paragraph> {
+    fn next_state(self, local_src: Local<SourceEvent>, diagnostics: &Option<Diagnostics>) -> NextResult<ParaState,Paragraph> {
+        if let SourceEvent::Char(c) = *local_src.data() {
+            match c {
+                '\t' | '\n' | '\x0C' | '\r' => {},
+                c if c.is_control() => warn(diagnostics,local_src.local(()),|| Warning::ControlChar(c)),
+                _ => {},
+            }
+        }
         match self {
             ParaState::Init => init(local_src),
             ParaState::First(v) => first(v,local_src),
@@ -80,7 +91,7 @@ fn init(local_src: Local<SourceEvent>) -> NextResult<ParaState,Paragraph> {
 fn create_para(current: OptVec<Local<ParserEvent<Paragraph>>>, end: Local<ParserEvent<Paragraph>>) -> Result<Local<ParserEvent<Paragraph>>,Error> {
     match current.into_iter().next() {
         Some(begin) => Local::from_segment(begin,end).map(|lc| lc.local(ParserEvent::Parsed(Paragraph))),
-        None => Err(Error::NoBegin),
+        None => Err(Error::NoBegin { local: end.local(()) }),
     }
 }
 
@@ -4,6 +4,7 @@ use crate::{
     SourceResult, Source,
     Breaker, Error,
     source::ParserSource,
+    Checkpoint,
 };
 
 pub trait Parser {
@@ -90,7 +91,8 @@ pub trait ParserExt: Parser + Sized {
 
 pub type ParserResult<D> =  Result<Option<Local<ParserEvent<D>>>,Error>;
 
-#[derive(Debug,Eq,PartialEq)]
+#[derive(Debug,Clone,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum ParserEvent<D> {
     Char(char),
     Breaker(Breaker),
@@ -158,7 +160,7 @@ where P: Parser,
 
         match self.parser.next_event(src) {
             Ok(olpe) => Ok(olpe),
-            Err(Error::EofInTag(raw)) => {
+            Err(Error::EofInTag { raw, .. }) => {
                 let mut iter = (self.func)(raw)?.into_iter();
                 match iter.next() {
                     Some(lpe) => {
@@ -259,6 +261,16 @@ where P: Parser,
 pub struct PipeBreaker<P> {
     parser: P,
 }
+impl<P: Checkpoint> Checkpoint for PipeBreaker<P> {
+    type State = P::State;
+
+    fn checkpoint(&self) -> Option<P::State> {
+        self.parser.checkpoint()
+    }
+    fn restore(&mut self, state: P::State) {
+        self.parser.restore(state);
+    }
+}
 impl<P> PipeParser for PipeBreaker<P>
 where P: Parser,
       P::Data: Into<Breaker>
@@ -318,6 +330,25 @@ where P: Parser,
         Ok(None)
     }
 }
+// no checkpoint in the middle of the events of a Parsed
+impl<P,I,F> Checkpoint for PipedWith<P,I,F>
+where P: Parser + Checkpoint,
+      I: IntoIterator<Item = SourceEvent>,
+      F: FnMut(<P as Parser>::Data) -> I
+{
+    type State = P::State;
+
+    fn checkpoint(&self) -> Option<P::State> {
+        match self.current_iter {
+            Some(_) => None,
+            None => self.parser.checkpoint(),
+        }
+    }
+    fn restore(&mut self, state: P::State) {
+        self.current_iter = None;
+        self.parser.restore(state);
+    }
+}
 
 
 pub struct PartialPipedWith<P,I,F>
@@ -395,6 +426,20 @@ pub struct Pipe<P1,P2> {
     parser: P1,
     pipe: P2,
 }
+impl<P1,P2> Checkpoint for Pipe<P1,P2>
+where P1: Checkpoint,
+      P2: Checkpoint
+{
+    type State = (P1::State,P2::State);
+
+    fn checkpoint(&self) -> Option<(P1::State,P2::State)> {
+        Some((self.parser.checkpoint()?,self.pipe.checkpoint()?))
+    }
+    fn restore(&mut self, (parser,pipe): (P1::State,P2::State)) {
+        self.parser.restore(parser);
+        self.pipe.restore(pipe);
+    }
+}
 impl<P1,P2> PipeParser for Pipe<P1,P2>
 where P1: PipeParser,
       P2: PipeParser
@@ -0,0 +1,234 @@
+use std::io::{
+    Read, ErrorKind,
+};
+
+use crate::{
+    Snip, Localize,
+    Source, SourceResult, Processed,
+    source::Sourcefy,
+    encoding::Utf8Mode,
+    Error, Checkpoint,
+};
+
+const CHUNK: usize = 8192;
+
+/*
+
+   Streaming UTF-8 source over any std::io::Read (files, pipes, stdin, BufReader ...)
+
+   Bytes are pulled in chunks and decoded char by char, a code point split between two reads
+   is kept in the buffer until the rest of it arrives. Offsets are the same as StrSource gives
+   for the whole text. Invalid UTF-8 is handled according to Utf8Mode (Strict by default).
+
+*/
+
+pub struct ReaderSource<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    position: usize,
+    chunk: usize,
+    eof: bool,
+    mode: Utf8Mode,
+    done: Processed,
+}
+impl<R: Read> ReaderSource<R> {
+    pub fn new(reader: R) -> ReaderSource<R> {
+        ReaderSource::with_chunk_size(CHUNK, reader)
+    }
+    pub fn with_chunk_size(chunk: usize, reader: R) -> ReaderSource<R> {
+        let chunk = chunk.max(4);
+        ReaderSource {
+            reader,
+            buffer: Vec::with_capacity(chunk),
+            position: 0,
+            chunk,
+            eof: false,
+            mode: Utf8Mode::Strict,
+            done: Processed::default(),
+        }
+    }
+    pub fn with_mode(mut self, mode: Utf8Mode) -> ReaderSource<R> {
+        self.mode = mode;
+        self
+    }
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn fill(&mut self) -> Result<(),Error> {
+        if self.position > 0 {
+            self.buffer.drain(.. self.position);
+            self.position = 0;
+        }
+        let len = self.buffer.len();
+        self.buffer.resize(len + self.chunk, 0);
+        let r = loop {
+            match self.reader.read(&mut self.buffer[len ..]) {
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                r => break r,
+            }
+        };
+        match r {
+            Ok(n) => {
+                self.buffer.truncate(len + n);
+                if n == 0 { self.eof = true; }
+                Ok(())
+            },
+            Err(e) => {
+                self.buffer.truncate(len);
+                Err(Error::Io { local: self.done.position(), error: e })
+            },
+        }
+    }
+}
+
+impl<R: Read> Source for ReaderSource<R> {
+    fn next_char(&mut self) -> SourceResult {
+        // a char takes at most 4 bytes
+        while (self.buffer.len() - self.position) < 4 && !self.eof {
+            self.fill()?;
+        }
+        let avail = &self.buffer[self.position ..];
+        if avail.is_empty() {
+            return Ok(None);
+        }
+        let (c,len) = match self.mode.decode(avail,self.done) {
+            Ok(r) => r,
+            Err((len,e)) => {
+                self.position += len;
+                self.done.bytes += len;
+                return Err(e);
+            },
+        };
+        let chars = Snip { offset: self.done.chars, length: 1 };
+        let bytes = Snip { offset: self.done.bytes, length: len };
+        let utf16 = Snip { offset: self.done.utf16, length: c.len_utf16() };
+        self.position += len;
+        self.done.chars += 1;
+        self.done.bytes += len;
+        self.done.utf16 += utf16.length;
+        Ok(Some(c.sourcefy().localize_utf16(chars,bytes,utf16)))
+    }
+    fn processed(&self) -> Processed {
+        self.done
+    }
+}
+// restarted by position (Snapshot::offset)
+impl<R> Checkpoint for ReaderSource<R> {
+    type State = ();
+
+    fn checkpoint(&self) -> Option<()> {
+        Some(())
+    }
+    fn restore(&mut self, _state: ()) {}
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use super::*;
+
+    struct ByteByByte<'s>(&'s [u8]);
+    impl<'s> Read for ByteByByte<'s> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match (self.0.split_first(), buf.is_empty()) {
+                (Some((b,rest)),false) => {
+                    buf[0] = *b;
+                    self.0 = rest;
+                    Ok(1)
+                },
+                _ => Ok(0),
+            }
+        }
+    }
+
+    struct Broken;
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("broken pipe"))
+        }
+    }
+
+    #[test]
+    fn same_as_str() {
+        let text = "<p>Привет, &#128175; 💯 мир!\n\u{200d}</p>";
+
+        for chunk in [4,5,7,CHUNK] {
+            let mut src = ReaderSource::with_chunk_size(chunk,text.as_bytes());
+            let mut res = text.into_source();
+            while let Some(local_event) = src.next_char().unwrap() {
+                match res.next_char().unwrap() {
+                    Some(ev) => assert_eq!(local_event,ev),
+                    None => panic!("reader has more events then str"),
+                }
+            }
+            assert_eq!(res.next_char().unwrap(),None);
+            assert_eq!(src.processed(),res.processed());
+        }
+    }
+
+    #[test]
+    fn split_code_points() {
+        let text = "💯 мир";
+        let mut src = ReaderSource::new(ByteByByte(text.as_bytes()))
+            .pipe(crate::entities::Builder::new().create().into_piped());
+
+        let mut res_iter = [
+            SourceEvent::Char('💯').localize_utf16(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 4 },Snip { offset: 0, length: 2 }),
+            SourceEvent::Char(' ').localize_utf16(Snip { offset: 1, length: 1 },Snip { offset: 4, length: 1 },Snip { offset: 2, length: 1 }),
+            SourceEvent::Char('м').localize_utf16(Snip { offset: 2, length: 1 },Snip { offset: 5, length: 2 },Snip { offset: 3, length: 1 }),
+            SourceEvent::Char('и').localize_utf16(Snip { offset: 3, length: 1 },Snip { offset: 7, length: 2 },Snip { offset: 4, length: 1 }),
+            SourceEvent::Char('р').localize_utf16(Snip { offset: 4, length: 1 },Snip { offset: 9, length: 2 },Snip { offset: 5, length: 1 }),
+        ].into_iter();
+
+        while let Some(local_event) = src.next_char().unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Source: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+    }
+
+    #[test]
+    fn errors() {
+        let mut src = ReaderSource::new(Broken);
+        match src.next_char() {
+            Err(Error::Io { local, error }) => {
+                assert_eq!(error.kind(),ErrorKind::Other);
+                assert_eq!(local,Processed::default().position());
+            },
+            r => panic!("unexpected result: {:?}",r),
+        }
+
+        let mut src = ReaderSource::new(&b"ok\xFF"[..]);
+        assert!(src.next_char().unwrap().is_some());
+        assert!(src.next_char().unwrap().is_some());
+        match src.next_char() {
+            Err(Error::InvalidUtf8 { local, .. }) => assert_eq!(local.bytes(),Snip { offset: 2, length: 1 }),
+            r => panic!("unexpected result: {:?}",r),
+        }
+
+        let mut src = ReaderSource::new(ByteByByte(b"ok\xD0"));
+        assert!(src.next_char().unwrap().is_some());
+        assert!(src.next_char().unwrap().is_some());
+        match src.next_char() {
+            Err(Error::InvalidUtf8 { local, .. }) => assert_eq!(local.bytes(),Snip { offset: 2, length: 1 }),
+            r => panic!("unexpected result: {:?}",r),
+        }
+        assert_eq!(src.next_char().unwrap(),None);
+        assert_eq!(src.processed(),Processed { chars: 2, bytes: 3, utf16: 2 });
+
+        let mut src = ReaderSource::with_chunk_size(4,ByteByByte(b"\xF0\x9F\x92\xD1\x8F")).with_mode(Utf8Mode::Lossy);
+        assert_eq!(src.next_char().unwrap(),Some(SourceEvent::Char('\u{FFFD}').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 3 })));
+        assert_eq!(src.next_char().unwrap(),Some(SourceEvent::Char('я').localize(Snip { offset: 1, length: 1 },Snip { offset: 3, length: 2 })));
+        assert_eq!(src.next_char().unwrap(),None);
+    }
+}
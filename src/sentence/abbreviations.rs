@@ -0,0 +1,29 @@
+/*
+
+   Abbreviations (lowercase, without the trailing dot):
+
+      ABBREVIATIONS     never end a sentence: "Mr. Smith", "т.е. это", "г. Москва"
+      FINAL             may end a sentence before a capitalized word: "and so on etc. Next", "и т.д. Потом"
+
+*/
+
+pub(in super) const EN_ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "st", "mt", "rev", "gen", "col", "lt", "sgt", "capt", "gov", "sen", "rep",
+    "e.g", "i.e", "cf", "vs", "viz", "approx", "fig", "figs", "vol", "p", "pp", "ch", "sec", "ed", "eds",
+    "jan", "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+    "a.m", "p.m", "u.s", "u.k",
+];
+
+pub(in super) const EN_FINAL: &[&str] = &[
+    "etc", "al", "inc", "ltd", "co", "corp", "jr", "no", "nos",
+];
+
+pub(in super) const RU_ABBREVIATIONS: &[&str] = &[
+    "т.е", "т.к", "т.н", "т.о", "напр", "см", "ср", "им", "ул", "пр", "пл", "д", "кв", "обл", "р-н", "г", "гг",
+    "рис", "табл", "стр", "с", "гл", "п", "ч", "проф", "акад", "доц", "тов", "гр", "св", "ок", "прим", "изд",
+    "янв", "февр", "авг", "сент", "окт", "нояб", "дек",
+];
+
+pub(in super) const RU_FINAL: &[&str] = &[
+    "т.д", "т.п", "др", "проч", "руб", "коп", "тыс", "млн", "млрд", "вв", "в",
+];
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+
+use crate::{
+    ParserResult,
+    Source,
+    Parser, Runtime, Breaker,
+    SourceEvent,ParserEvent,
+    PipeParser, SourceResult,
+    RuntimeState, Checkpoint,
+};
+
+use super::{
+    abbreviations::{EN_ABBREVIATIONS,EN_FINAL,RU_ABBREVIATIONS,RU_FINAL},
+    state::{SentState,Sentence},
+    unicode::UnicodeState,
+};
+
+/*
+
+    Terminal punctuation {closing quote or bracket}* {whitespace}+ X = Breaker::Sentence
+
+    X is not lowercase, except:
+       abbreviation + "." X                     no sentence end ("Mr. Smith", "т.е. это")
+       final abbreviation + "." X               sentence end only if X is uppercase ("etc. Next")
+       single uppercase letter + "." X          no sentence end (initials: "A. S. Pushkin")
+
+    Sentence event covers the punctuation and the trailing whitespace.
+
+    Builder::unicode(): sentence boundaries of UAX #29 instead of the rules above (no abbreviations).
+
+*/
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+pub enum Language {
+    English,
+    Russian,
+}
+
+#[derive(Debug,Clone,Default)]
+pub struct Builder {
+    unicode: bool,
+    properties: SentenceProperties,
+}
+impl Builder {
+    pub fn new() -> Builder {
+        Builder{
+            unicode: false,
+            properties: SentenceProperties::default(),
+        }
+    }
+    pub fn unicode() -> Builder {
+        Builder{
+            unicode: true,
+            properties: SentenceProperties::default(),
+        }
+    }
+    // abbreviations of the language
+    pub fn with_language(mut self, lang: Language) -> Builder {
+        let (abbrs,finals) = match lang {
+            Language::English => (EN_ABBREVIATIONS,EN_FINAL),
+            Language::Russian => (RU_ABBREVIATIONS,RU_FINAL),
+        };
+        for abbr in abbrs {
+            self = self.with_abbreviation(abbr);
+        }
+        for abbr in finals {
+            self = self.with_final_abbreviation(abbr);
+        }
+        self
+    }
+    // never ends a sentence, with or without the trailing dot: "ул" or "ул."
+    pub fn with_abbreviation(mut self, abbr: &str) -> Builder {
+        self.properties.abbreviations.insert(SentenceProperties::key(abbr),false);
+        self
+    }
+    // ends a sentence if the next word is capitalized
+    pub fn with_final_abbreviation(mut self, abbr: &str) -> Builder {
+        self.properties.abbreviations.insert(SentenceProperties::key(abbr),true);
+        self
+    }
+    pub fn create(self) -> Sentences {
+        Sentences(match self.unicode {
+            false => InnerSentences::Rules(Runtime::new(self.properties)),
+            true => InnerSentences::Unicode(Runtime::new(())),
+        })
+    }
+}
+
+#[derive(Debug,Clone,Default)]
+pub(in super) struct SentenceProperties {
+    abbreviations: HashMap<String,bool>, // may end a sentence
+}
+impl SentenceProperties {
+    fn key(abbr: &str) -> String {
+        abbr.trim_end_matches('.').to_lowercase()
+    }
+    pub(in super) fn abbreviation(&self, word: &str) -> Option<bool> {
+        self.abbreviations.get(&SentenceProperties::key(word)).copied()
+    }
+}
+
+
+pub struct Sentences(InnerSentences);
+
+enum InnerSentences {
+    Rules(Runtime<SentState,Sentence,SentenceProperties>),
+    Unicode(Runtime<UnicodeState,Sentence,()>),
+}
+
+impl Parser for Sentences {
+    type Data = Sentence;
+
+    fn next_event<S: Source>(&mut self, src: &mut S) -> ParserResult<Sentence> {
+        match &mut self.0 {
+            InnerSentences::Rules(runtime) => runtime.next_event(src),
+            InnerSentences::Unicode(runtime) => runtime.next_event(src),
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct SentCheckpoint(InnerCheckpoint);
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+enum InnerCheckpoint {
+    Rules(RuntimeState<SentState,Sentence>),
+    Unicode(RuntimeState<UnicodeState,Sentence>),
+}
+
+impl Checkpoint for Sentences {
+    type State = SentCheckpoint;
+
+    fn checkpoint(&self) -> Option<SentCheckpoint> {
+        Some(SentCheckpoint(match &self.0 {
+            InnerSentences::Rules(runtime) => InnerCheckpoint::Rules(runtime.checkpoint()?),
+            InnerSentences::Unicode(runtime) => InnerCheckpoint::Unicode(runtime.checkpoint()?),
+        }))
+    }
+    // checkpoint of the other mode is ignored
+    fn restore(&mut self, state: SentCheckpoint) {
+        match (&mut self.0,state.0) {
+            (InnerSentences::Rules(runtime),InnerCheckpoint::Rules(state)) => runtime.restore(state),
+            (InnerSentences::Unicode(runtime),InnerCheckpoint::Unicode(state)) => runtime.restore(state),
+            _ => {},
+        }
+    }
+}
+
+impl PipeParser for Sentences {
+    fn next_char<S: Source>(&mut self, src: &mut S) -> SourceResult {
+        Ok(match self.next_event(src)? {
+            Some(local_pe) => {
+                let (local,pe) = local_pe.into_inner();
+                Some(local.local(match pe {
+                    ParserEvent::Char(c) => SourceEvent::Char(c),
+                    ParserEvent::Breaker(b) => SourceEvent::Breaker(b),
+                    ParserEvent::Parsed(Sentence) => SourceEvent::Breaker(Breaker::Sentence),
+                }))
+            },
+            None => None,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use super::*;
+
+    fn split(builder: Builder, text: &str) -> Vec<String> {
+        let mut src = text.into_source();
+        let mut parser = builder.create();
+        let mut res = vec![String::new()];
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            match local_event.data() {
+                ParserEvent::Char(c) => res.last_mut().unwrap().push(*c),
+                ParserEvent::Breaker(_) => res.last_mut().unwrap().push(' '),
+                ParserEvent::Parsed(Sentence) => res.push(String::new()),
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn basic() {
+        let mut src = "Hi! \"Ok.\"  Да".into_source();
+        let mut parser = Builder::new().create();
+
+        let mut res_iter = [
+            ParserEvent::Char('H').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            ParserEvent::Char('i').localize(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 1 }),
+            ParserEvent::Parsed(Sentence).localize(Snip { offset: 2, length: 2 },Snip { offset: 2, length: 2 }),
+            ParserEvent::Char('"').localize(Snip { offset: 4, length: 1 },Snip { offset: 4, length: 1 }),
+            ParserEvent::Char('O').localize(Snip { offset: 5, length: 1 },Snip { offset: 5, length: 1 }),
+            ParserEvent::Char('k').localize(Snip { offset: 6, length: 1 },Snip { offset: 6, length: 1 }),
+            ParserEvent::Parsed(Sentence).localize(Snip { offset: 7, length: 4 },Snip { offset: 7, length: 4 }),
+            ParserEvent::Char('Д').localize(Snip { offset: 11, length: 1 },Snip { offset: 11, length: 2 }),
+            ParserEvent::Char('а').localize(Snip { offset: 12, length: 1 },Snip { offset: 13, length: 2 }),
+        ].into_iter();
+
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Parser: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn english() {
+        let text = "Mr. Smith met Dr. J. R. Brown, e.g. at home. They talked about tea, coffee, etc. Then left... And 3.14 is pi? Yes! the end";
+        assert_eq!(split(Builder::new().with_language(Language::English),text),vec![
+            "Mr. Smith met Dr. J. R. Brown, e.g. at home",
+            "They talked about tea, coffee, etc",
+            "Then left",
+            "And 3.14 is pi",
+            "Yes! the end",
+        ]);
+        assert_eq!(split(Builder::new().with_language(Language::English),"The answer is no. We left. See No. 5 now."),vec![
+            "The answer is no",
+            "We left",
+            "See No. 5 now",
+            "",
+        ]);
+        assert_eq!(split(Builder::new(),"Mr. Smith left. Bye."),vec!["Mr","Smith left","Bye",""]);
+    }
+
+    #[test]
+    fn russian() {
+        let text = "Он живёт в г. Москва, т.е. в столице. Купил хлеб, молоко и т.д. Потом ушёл. «Кто там?» Никто.\n\nКонец";
+        assert_eq!(split(Builder::new().with_language(Language::Russian),text),vec![
+            "Он живёт в г. Москва, т.е. в столице",
+            "Купил хлеб, молоко и т.д",
+            "Потом ушёл",
+            "«Кто там",
+            "Никто",
+            "Конец",
+        ]);
+    }
+
+    #[test]
+    fn unicode() {
+        let text = "Mr. Smith left, i.e. went home. «Кто там?» Никто.\n\nКонец";
+        assert_eq!(split(Builder::unicode(),text),vec![
+            "Mr",
+            "Smith left, i.e. went home",
+            "«Кто там",
+            "Никто",
+            "",
+            "Конец",
+        ]);
+        assert_eq!(split(Builder::unicode(),"etc. and 5 apples? No"),vec!["etc. and 5 apples","No"]);
+    }
+
+    #[test]
+    fn unicode_long() {
+        let text = "word ".repeat(4000);
+        assert_eq!(split(Builder::unicode(),&format!("{}. Next",text)),vec![text.trim_end(),"Next"]);
+        let res = split(Builder::unicode(),&format!("{}end","no terminal\n".repeat(2000)));
+        assert_eq!(res.len(),2001);
+        assert!(res[.. 2000].iter().all(|s| s == "no terminal"));
+
+        let res = split(Builder::unicode(),&format!("Hi.{}Next",' '.to_string().repeat(5000)));
+        assert_eq!((res.len(),res[0].as_str(),res[1].trim_start()),(2,"Hi","Next"));
+        let res = split(Builder::unicode(),&format!("Hi{}X",". ".repeat(20000)));
+        assert_eq!((res.first().unwrap().as_str(),res.last().unwrap().as_str()),("Hi","X"));
+        assert!(res[1 .. res.len() - 1].iter().all(String::is_empty));
+    }
+
+    #[test]
+    fn unicode_breakers() {
+        let mut src = "Да. ".into_source()
+            .chain(Breaker::Paragraph.into_source_as("\n"))
+            .chain("Нет".into_source());
+        let mut parser = Builder::unicode().create();
+
+        let mut res_iter = [
+            ParserEvent::Char('Д').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 2 }),
+            ParserEvent::Char('а').localize(Snip { offset: 1, length: 1 },Snip { offset: 2, length: 2 }),
+            ParserEvent::Parsed(Sentence).localize(Snip { offset: 2, length: 2 },Snip { offset: 4, length: 2 }),
+            ParserEvent::Breaker(Breaker::Paragraph).localize(Snip { offset: 4, length: 1 },Snip { offset: 6, length: 1 }),
+            ParserEvent::Char('Н').localize(Snip { offset: 5, length: 1 },Snip { offset: 7, length: 2 }),
+            ParserEvent::Char('е').localize(Snip { offset: 6, length: 1 },Snip { offset: 9, length: 2 }),
+            ParserEvent::Char('т').localize(Snip { offset: 7, length: 1 },Snip { offset: 11, length: 2 }),
+        ].into_iter();
+
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Parser: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn piped() {
+        let mut src = "Раз. Два".into_source()
+            .pipe(Builder::new().create());
+        let mut res = Vec::new();
+        while let Some(local_se) = src.next_char().unwrap() {
+            res.push(*local_se.data());
+        }
+        assert_eq!(res,vec![
+            SourceEvent::Char('Р'), SourceEvent::Char('а'), SourceEvent::Char('з'),
+            SourceEvent::Breaker(Breaker::Sentence),
+            SourceEvent::Char('Д'), SourceEvent::Char('в'), SourceEvent::Char('а'),
+        ]);
+    }
+}
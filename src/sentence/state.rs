@@ -0,0 +1,158 @@
+use crate::{
+    Local, ParserEvent,
+    NextResult, Next, StateMachine,
+    SourceEvent, Breaker,
+};
+
+use super::parser::SentenceProperties;
+
+#[derive(Debug,Clone,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Sentence;
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub(in super) enum SentState {
+    Init(String),          // word before the current char
+    Terminal(Candidate),   // terminal punctuation, closing quotes and brackets
+    Space(Candidate),      // whitespace after the terminal punctuation
+}
+impl Default for SentState {
+    fn default() -> SentState {
+        SentState::Init(String::new())
+    }
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub(in super) struct Candidate {
+    word: String,                    // word before the terminal punctuation (for abbreviations)
+    end: String,                     // terminal punctuation, closing quotes and brackets
+    events: Vec<Local<SourceEvent>>,
+}
+impl Candidate {
+    // sentence ends before the next word beginning with the char
+    fn is_boundary(&self, c: char, props: &SentenceProperties) -> bool {
+        if self.end == "." {
+            if let Some(may_end) = props.abbreviation(&self.word) {
+                return may_end && c.is_uppercase();
+            }
+            let mut chars = self.word.chars();
+            if let (Some(initial),None) = (chars.next(),chars.next()) && initial.is_uppercase() {
+                return false;
+            }
+        }
+        !c.is_lowercase()
+    }
+    fn sentence(self, mut next: Next<SentState,Sentence>) -> NextResult<SentState,Sentence> {
+        let mut iter = self.events.into_iter();
+        if let Some(first) = iter.next() {
+            let local = match iter.last() {
+                Some(last) => Local::from_segment(first,last)?,
+                None => first,
+            };
+            next.push_event(local.local(ParserEvent::Parsed(Sentence)));
+        }
+        Ok(next)
+    }
+    // not a sentence end: events as is, word is continued after a single dot ("т.е.", "e.g.", "3.14")
+    fn failed(self) -> (String,Next<SentState,Sentence>) {
+        let mut next = Next::empty();
+        for lse in self.events {
+            next.push_event(lse.map(|se| se.into()));
+        }
+        let word = match self.end == "." {
+            true => self.word + ".",
+            false => String::new(),
+        };
+        (word,next)
+    }
+}
+
+pub(in super) fn is_terminal(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…' | '‼' | '⁇' | '⁈' | '⁉' | '。' | '！' | '？')
+}
+pub(in super) fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | '»' | '”' | '’' | '›' | ')' | ']' | '}')
+}
+fn is_space(se: &SourceEvent) -> bool {
+    match se {
+        SourceEvent::Char(c) => c.is_whitespace(),
+        SourceEvent::Breaker(b) => matches!(b, Breaker::None | Breaker::Space | Breaker::Word | Breaker::Line),
+    }
+}
+
+impl StateMachine for SentState {
+    type Context = SentenceProperties;
+    type Data = Sentence;
+
+    fn eof(self, _props: &SentenceProperties) -> NextResult<SentState,Sentence> {
+        match self {
+            SentState::Init(_) => Ok(Next::empty()),
+            SentState::Terminal(cand) |
+            SentState::Space(cand) => cand.sentence(Next::empty()),
+        }
+    }
+    fn next_state(self, local_src: Local<SourceEvent>, props: &SentenceProperties) -> NextResult<SentState,Sentence> {
+        match self {
+            SentState::Init(word) => init(word,local_src,Next::empty()),
+            SentState::Terminal(cand) => terminal(cand,local_src,props),
+            SentState::Space(cand) => space(cand,local_src,props),
+        }
+    }
+}
+
+fn init(mut word: String, local_src: Local<SourceEvent>, mut next: Next<SentState,Sentence>) -> NextResult<SentState,Sentence> {
+    Ok(match *local_src.data() {
+        SourceEvent::Char(c) if is_terminal(c) => next.with_state(SentState::Terminal(Candidate {
+            word,
+            end: c.to_string(),
+            events: vec![local_src],
+        })),
+        SourceEvent::Char(c) => {
+            match c.is_alphanumeric() || (c == '-') {
+                true => word.push(c),
+                false => word.clear(),
+            }
+            next.push_event(local_src.map(|se| se.into()));
+            next.with_state(SentState::Init(word))
+        },
+        SourceEvent::Breaker(_) => {
+            next.push_event(local_src.map(|se| se.into()));
+            next
+        },
+    })
+}
+
+fn terminal(mut cand: Candidate, local_src: Local<SourceEvent>, props: &SentenceProperties) -> NextResult<SentState,Sentence> {
+    Ok(match *local_src.data() {
+        SourceEvent::Char(c) if is_terminal(c) || is_closing(c) => {
+            cand.end.push(c);
+            cand.events.push(local_src);
+            Next::empty().with_state(SentState::Terminal(cand))
+        },
+        se if is_space(&se) => space(cand,local_src,props)?,
+        SourceEvent::Breaker(_) => cand.sentence(Next::empty())?.with_event(local_src.map(|se| se.into())),
+        SourceEvent::Char(_) => {
+            let (word,next) = cand.failed();
+            init(word,local_src,next)?
+        },
+    })
+}
+
+fn space(mut cand: Candidate, local_src: Local<SourceEvent>, props: &SentenceProperties) -> NextResult<SentState,Sentence> {
+    Ok(match *local_src.data() {
+        se if is_space(&se) => {
+            cand.events.push(local_src);
+            Next::empty().with_state(SentState::Space(cand))
+        },
+        SourceEvent::Breaker(_) => cand.sentence(Next::empty())?.with_event(local_src.map(|se| se.into())),
+        SourceEvent::Char(c) => match cand.is_boundary(c,props) {
+            true => init(String::new(),local_src,cand.sentence(Next::empty())?)?,
+            false => {
+                let (_,next) = cand.failed();
+                init(String::new(),local_src,next)?
+            },
+        },
+    })
+}
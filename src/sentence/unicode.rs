@@ -0,0 +1,165 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    Error, Local, ParserEvent,
+    NextResult, Next, StateMachine,
+    SourceEvent, Breaker,
+};
+
+use super::state::{Sentence,is_terminal,is_closing};
+
+/*
+
+   Sentence boundaries (UAX #29): https://www.unicode.org/reports/tr29/#Sentence_Boundaries
+
+   Breakers are segmented as chars: Space and Word as a space, Line as a line feed (paragraph separator
+   of the algorithm), None as a word joiner (ignored by the algorithm). Sentence, Paragraph and Section
+   are hard boundaries, they are passed as they are.
+
+   A segment ends with terminal punctuation, closing quotes and brackets and whitespace, these are replaced
+   by a single ParserEvent::Parsed(Sentence), as in the rule-based mode.
+
+   Boundaries look ahead no further than the next segment ("etc. 5 apples"), so all but the last two
+   segments of the buffered events are final. The lookahead ends at the next letter or paragraph separator,
+   so the last segment is final too (except its tail) if there is a letter after its last terminal punctuation
+   or if it ends with a paragraph separator. So the buffer is segmented only while a boundary is unsettled,
+   and is cut at letters, paragraph separators and at MAX_BUFFER events.
+
+   A tail longer than MAX_TAIL events (padding after the terminal punctuation: "Hi." and 500 spaces, ". . . .")
+   is final, followed by a boundary: segmentation of a long tail is slow (quadratic for spaces).
+
+*/
+
+const MAX_BUFFER: usize = 128;
+const MAX_TAIL: usize = 32;
+
+#[derive(Debug,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub(in super) struct UnicodeState {
+    events: Vec<Local<SourceEvent>>,
+    open: bool, // events may end a sentence (terminal punctuation or paragraph separator)
+    tail: usize, // trailing events of the tail
+}
+
+fn as_char(se: &SourceEvent) -> char {
+    match se {
+        SourceEvent::Char(c) => *c,
+        SourceEvent::Breaker(Breaker::None) => '\u{2060}',
+        SourceEvent::Breaker(Breaker::Space) |
+        SourceEvent::Breaker(Breaker::Word) => ' ',
+        SourceEvent::Breaker(Breaker::Line) => '\n',
+        SourceEvent::Breaker(Breaker::Sentence) |
+        SourceEvent::Breaker(Breaker::Paragraph) |
+        SourceEvent::Breaker(Breaker::Section) => '\u{2029}',
+    }
+}
+
+fn is_para_sep(c: char) -> bool {
+    matches!(c,'\n' | '\r' | '\u{85}' | '\u{2028}' | '\u{2029}')
+}
+
+fn may_end(c: char) -> bool {
+    is_terminal(c) || is_para_sep(c)
+}
+
+fn is_tail(se: &SourceEvent) -> bool {
+    let c = as_char(se);
+    c.is_whitespace() || (c == '\u{2060}') || is_terminal(c) || is_closing(c)
+}
+
+// events of the segment, the tail replaced by a sentence if the segment is followed by a boundary
+fn segment(mut events: Vec<Local<SourceEvent>>, followed: bool, next: &mut Next<UnicodeState,Sentence>) -> Result<(),Error> {
+    let body = events.len() - events.iter().rev().take_while(|lse| is_tail(lse.data())).count();
+    let tail = events.split_off(body);
+    let terminal = tail.iter().any(|lse| is_terminal(as_char(lse.data())));
+    let para_sep = tail.iter().any(|lse| is_para_sep(as_char(lse.data())));
+    for lse in events {
+        next.push_event(lse.map(|se| se.into()));
+    }
+    match (tail.first(),tail.last()) {
+        (Some(first),Some(last)) if terminal || (followed && para_sep) => {
+            next.push_event(Local::from_segment(*first,*last)?.local(ParserEvent::Parsed(Sentence)));
+        },
+        _ => for lse in tail {
+            next.push_event(lse.map(|se| se.into()));
+        },
+    }
+    Ok(())
+}
+
+// the boundary before the segment and the segment body are final
+fn settled(events: &[Local<SourceEvent>]) -> bool {
+    let letter = |events: &[Local<SourceEvent>]| events.iter().any(|lse| as_char(lse.data()).is_alphabetic());
+    match events.iter().rposition(|lse| may_end(as_char(lse.data()))) {
+        Some(i) if (i + 1) == events.len() => is_para_sep(as_char(events[i].data())),
+        Some(i) => letter(&events[i + 1 ..]),
+        None => letter(events),
+    }
+}
+
+impl UnicodeState {
+    // lengths (in events) of the sentence segments of the buffer
+    fn segments(&self) -> Vec<usize> {
+        let text: String = self.events.iter().map(|lse| as_char(lse.data())).collect();
+        text.split_sentence_bounds().map(|s| s.chars().count()).collect()
+    }
+    // emits all segments but the last `keep`, and the body of the last one if it is settled
+    fn flush(mut self, keep: usize, mut next: Next<UnicodeState,Sentence>) -> NextResult<UnicodeState,Sentence> {
+        let forced = (keep > 0) && (self.tail > MAX_TAIL);
+        let keep = if forced { 0 } else { keep };
+        let segments = match self.open {
+            true => self.segments(),
+            false => vec![self.events.len()], // no boundaries
+        };
+        let last = segments.last().copied().unwrap_or(0);
+        let settled = (keep > 0) && (!self.open || (self.events.len() >= MAX_BUFFER) || settled(&self.events[self.events.len() - last ..]));
+        let ready = segments.len().saturating_sub(if settled { 1 } else { keep });
+        let mut rest = self.events.split_off(segments[.. ready].iter().sum());
+        let mut events = self.events.into_iter();
+        for (i,len) in segments[.. ready].iter().enumerate() {
+            let followed = forced || ((i + 1) < segments.len());
+            segment(events.by_ref().take(*len).collect(),followed,&mut next)?;
+        }
+        if settled {
+            // the last char of the body is kept as a context of the terminal punctuation ("U.S.")
+            let body = rest.len() - rest.iter().rev().take_while(|lse| is_tail(lse.data())).count();
+            let tail = rest.split_off(body.saturating_sub(1));
+            for lse in rest {
+                next.push_event(lse.map(|se| se.into()));
+            }
+            rest = tail;
+        }
+        let open = rest.iter().any(|lse| may_end(as_char(lse.data())));
+        let tail = rest.iter().rev().take_while(|lse| is_tail(lse.data())).count();
+        Ok(next.with_state(UnicodeState { events: rest, open, tail }))
+    }
+}
+
+impl StateMachine for UnicodeState {
+    type Context = ();
+    type Data = Sentence;
+
+    fn eof(self, _props: &()) -> NextResult<UnicodeState,Sentence> {
+        self.flush(0,Next::empty())
+    }
+    fn next_state(mut self, local_src: Local<SourceEvent>, _props: &()) -> NextResult<UnicodeState,Sentence> {
+        match *local_src.data() {
+            SourceEvent::Breaker(b @ Breaker::Sentence) |
+            SourceEvent::Breaker(b @ Breaker::Paragraph) |
+            SourceEvent::Breaker(b @ Breaker::Section) => {
+                let mut next = self.flush(0,Next::empty())?;
+                next.push_event(local_src.local(ParserEvent::Breaker(b)));
+                Ok(next)
+            },
+            _ => {
+                self.open |= may_end(as_char(local_src.data()));
+                self.tail = match is_tail(local_src.data()) {
+                    true => self.tail + 1,
+                    false => 0,
+                };
+                self.events.push(local_src);
+                self.flush(2,Next::empty())
+            },
+        }
+    }
+}
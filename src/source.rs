@@ -1,16 +1,54 @@
+use std::collections::VecDeque;
+
+use opt_struct::OptVec;
+
 use crate::{
     Snip, Localize, Local,
-    PipeParser,
+    PipeParser, Checkpoint,
     Error,
+    graphemes::Graphemes,
+    normalize::{Normalize,NormalForm},
+    case_fold::CaseFold,
 };
 
 #[derive(Debug,Clone,Copy,Default,PartialEq,Eq,PartialOrd,Ord,Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Processed {
     pub chars: usize,
     pub bytes: usize,
+    pub utf16: usize,
+}
+
+impl Processed {
+    pub fn of(s: &str) -> Processed {
+        Processed {
+            chars: s.chars().count(),
+            bytes: s.len(),
+            utf16: s.encode_utf16().count(),
+        }
+    }
+    // zero-length Local at the end of processed text
+    pub fn position(&self) -> Local<()> {
+        ().localize_utf16(
+            Snip { offset: self.chars, length: 0 },
+            Snip { offset: self.bytes, length: 0 },
+            Snip { offset: self.utf16, length: 0 },
+        )
+    }
+    // offset of the subslice in the parent str
+    pub fn offset_in(parent: &str, slice: &str) -> Option<Processed> {
+        let begin = (slice.as_ptr() as usize).checked_sub(parent.as_ptr() as usize)?;
+        match (begin + slice.len() <= parent.len()) && parent.is_char_boundary(begin) {
+            true => Some(Processed::of(&parent[.. begin])),
+            false => None,
+        }
+    }
 }
 
 pub trait Source {
+    // Ok(None) is the end of input. Err(Error::Pending) is not a failure: there is no data yet
+    // (only sources fed by chunks return it, ChunkSource), the source and the parsers over it keep
+    // their state and next_char can be called again after more data is pushed (Error::is_pending).
     fn next_char(&mut self) -> SourceResult;
     fn processed(&self) -> Processed;
 }
@@ -28,6 +66,7 @@ pub trait Sourcefy {
 pub type SourceResult =  Result<Option<Local<SourceEvent>>,Error>;
 
 #[derive(Debug,Clone,Copy,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 // Inclusive: Sentence = sentence breaker + word breaker, etc.
 pub enum Breaker {
     None,
@@ -40,6 +79,7 @@ pub enum Breaker {
 }
 
 #[derive(Debug,Clone,Copy,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum SourceEvent {
     Char(char),
     Breaker(Breaker),
@@ -59,7 +99,8 @@ impl Breaker {
     pub fn into_source_as(self, s: &str) -> OptSource {
         let blen = s.len();
         let clen = s.chars().count();
-        OptSource::new(self.sourcefy().localize(Snip{ offset: 0, length: clen }, Snip{ offset: 0, length: blen }))
+        let ulen = s.encode_utf16().count();
+        OptSource::new(self.sourcefy().localize_utf16(Snip{ offset: 0, length: clen }, Snip{ offset: 0, length: blen }, Snip{ offset: 0, length: ulen }))
     }
 }
 
@@ -67,7 +108,8 @@ impl IntoSource for char {
     type Source = OptSource;
     fn into_source(self) -> Self::Source {
         let blen = self.len_utf8();
-        OptSource::new(self.sourcefy().localize(Snip{ offset: 0, length: 1 }, Snip{ offset: 0, length: blen }))
+        let ulen = self.len_utf16();
+        OptSource::new(self.sourcefy().localize_utf16(Snip{ offset: 0, length: 1 }, Snip{ offset: 0, length: blen }, Snip{ offset: 0, length: ulen }))
     }
 }
 
@@ -137,6 +179,7 @@ impl Source for OptSource {
         if let Some(local_se) = &r {
             self.done.chars += local_se.chars().length;
             self.done.bytes += local_se.bytes().length;
+            self.done.utf16 += local_se.utf16().length;
         }
         Ok(r)
     }
@@ -176,9 +219,11 @@ impl<'s> Source for StrSource<'s> {
         Ok(self.source.next().map(|(char_index,(byte_index,c))| {
             let chars = Snip { offset: char_index, length: 1 };
             let bytes = Snip { offset: byte_index, length: c.len_utf8() };
-            let r = c.sourcefy().localize(chars,bytes);
+            let utf16 = Snip { offset: self.done.utf16, length: c.len_utf16() };
+            let r = c.sourcefy().localize_utf16(chars,bytes,utf16);
             self.done.chars += r.chars().length;
             self.done.bytes += r.bytes().length;
+            self.done.utf16 += r.utf16().length;
             r
         }))
     }
@@ -186,6 +231,15 @@ impl<'s> Source for StrSource<'s> {
         self.done
     }
 }
+// restarted by position (Snapshot::offset)
+impl<'s> Checkpoint for StrSource<'s> {
+    type State = ();
+
+    fn checkpoint(&self) -> Option<()> {
+        Some(())
+    }
+    fn restore(&mut self, _state: ()) {}
+}
 
 impl<T: Source> SourceExt for T {}
 
@@ -213,24 +267,29 @@ pub trait SourceExt: Source + Sized {
         }
     }
     fn into_separator(self) -> IntoSeparator<Self> {
+        self.into_separator_with(Classifier::new())
+    }
+    fn into_separator_with(self, classifier: Classifier) -> IntoSeparator<Self> {
         IntoSeparator {
             source: self,
+            classifier,
+            pending: None,
         }
     }
     fn merge_separators(self) -> MergeSeparator<Self> {
+        self.merge_separators_with(MergePolicy::new())
+    }
+    fn merge_separators_with(self, policy: MergePolicy) -> MergeSeparator<Self> {
         MergeSeparator {
             source: self,
+            policy,
             buffer: None,
             current: None,
         }
     }
-    /*fn shift(self, char_offset: usize, byte_offset: usize) -> Shift<Self> {
-        Shift {
-            source: self,
-            char_offset,
-            byte_offset,
-        }
-    }*/
+    fn with_offset(self, offset: Processed) -> Shift<Self> {
+        Shift::new(self,offset)
+    }
     fn chain<S: Source>(self, chained: S) -> Chain<Self,S> {
         Chain {
             inner: InnerChain::First(self),
@@ -243,6 +302,30 @@ pub trait SourceExt: Source + Sized {
             mapper,
         }
     }
+    fn graphemes(self) -> Graphemes<Self> {
+        Graphemes::new(self)
+    }
+    fn normalize(self, form: NormalForm) -> Normalize<Self> {
+        Normalize::new(self,form)
+    }
+    fn flat_map<M>(self, mapper: M) -> FlatMap<Self,M>
+    where M: FlatMapper
+    {
+        FlatMap {
+            source: self,
+            mapper,
+            pending: Vec::new(),
+            locals: Vec::new(),
+            ready: VecDeque::new(),
+            eof: false,
+        }
+    }
+    fn case_fold(self) -> FlatMap<Self,CaseFold> {
+        self.flat_map(CaseFold::new())
+    }
+    fn case_fold_with(self, fold: CaseFold) -> FlatMap<Self,CaseFold> {
+        self.flat_map(fold)
+    }
 }
 
 pub trait CharMapper {
@@ -253,6 +336,38 @@ pub trait Mapper {
     fn map(&mut self, se: &SourceEvent) -> Option<SourceEvent>;
 }
 
+/*
+
+   One-to-many / many-to-one mapping:
+
+      mapper gets all the pending (not yet consumed) events and returns
+         Mapped::Wait to get one more event (ignored at eof, the first pending event is passed as it is)
+         Mapped::Emit { consumed, events } to replace the first `consumed` pending events (at least one) with `events`
+
+      all emitted events get the Local of consumed events (from the first to the last one),
+      no events means the consumed events are removed
+
+*/
+
+pub enum Mapped {
+    Wait,
+    Emit {
+        consumed: usize,
+        events: OptVec<SourceEvent>,
+    },
+}
+
+pub trait FlatMapper {
+    fn map(&mut self, pending: &[SourceEvent], eof: bool) -> Mapped;
+}
+impl<F> FlatMapper for F
+where F: FnMut(&[SourceEvent], bool) -> Mapped
+{
+    fn map(&mut self, pending: &[SourceEvent], eof: bool) -> Mapped {
+        (self)(pending,eof)
+    }
+}
+
 pub struct MapChar<S,M>
 {
     source: S,
@@ -272,6 +387,18 @@ where S: Source,
         self.source.processed()
     }
 }
+impl<S,M> Checkpoint for MapChar<S,M>
+where S: Checkpoint
+{
+    type State = S::State;
+
+    fn checkpoint(&self) -> Option<S::State> {
+        self.source.checkpoint()
+    }
+    fn restore(&mut self, state: S::State) {
+        self.source.restore(state);
+    }
+}
         
 
 pub struct Map<S,M>
@@ -299,6 +426,96 @@ where S: Source,
         self.source.processed()
     }
 }
+impl<S,M> Checkpoint for Map<S,M>
+where S: Checkpoint
+{
+    type State = S::State;
+
+    fn checkpoint(&self) -> Option<S::State> {
+        self.source.checkpoint()
+    }
+    fn restore(&mut self, state: S::State) {
+        self.source.restore(state);
+    }
+}
+
+pub struct FlatMap<S,M>
+{
+    source: S,
+    mapper: M,
+    pending: Vec<SourceEvent>,
+    locals: Vec<Local<()>>,
+    ready: VecDeque<Local<SourceEvent>>,
+    eof: bool,
+}
+impl<S: Source,M> FlatMap<S,M> {
+    fn pull(&mut self) -> Result<(),Error> {
+        match self.source.next_char()? {
+            Some(local_se) => {
+                let (local,se) = local_se.into_inner();
+                self.pending.push(se);
+                self.locals.push(local);
+            },
+            None => self.eof = true,
+        }
+        Ok(())
+    }
+}
+impl<S,M> Source for FlatMap<S,M>
+where S: Source,
+      M: FlatMapper
+{
+    fn next_char(&mut self) -> SourceResult {
+        loop {
+            if let Some(local_se) = self.ready.pop_front() {
+                break Ok(Some(local_se));
+            }
+            if self.pending.is_empty() {
+                if self.eof {
+                    break Ok(None);
+                }
+                self.pull()?;
+                continue;
+            }
+            let (consumed,events) = match self.mapper.map(&self.pending,self.eof) {
+                Mapped::Wait if !self.eof => {
+                    self.pull()?;
+                    continue;
+                },
+                Mapped::Wait => (1,OptVec::One(self.pending[0])),
+                Mapped::Emit { consumed, events } => (consumed.clamp(1,self.pending.len()),events),
+            };
+            let local = Local::from_segment(self.locals[0],self.locals[consumed-1])?;
+            self.pending.drain(.. consumed);
+            self.locals.drain(.. consumed);
+            for se in events {
+                self.ready.push_back(local.local(se));
+            }
+        }
+    }
+    fn processed(&self) -> Processed {
+        self.source.processed()
+    }
+}
+// the mapper is a part of the state: it can be stateful (final sigma of CaseFold)
+impl<S,M> Checkpoint for FlatMap<S,M>
+where S: Checkpoint,
+      M: Clone
+{
+    type State = (S::State,M,Vec<SourceEvent>,Vec<Local<()>>,VecDeque<Local<SourceEvent>>,bool);
+
+    fn checkpoint(&self) -> Option<Self::State> {
+        Some((self.source.checkpoint()?,self.mapper.clone(),self.pending.clone(),self.locals.clone(),self.ready.clone(),self.eof))
+    }
+    fn restore(&mut self, (source,mapper,pending,locals,ready,eof): Self::State) {
+        self.source.restore(source);
+        self.mapper = mapper;
+        self.pending = pending;
+        self.locals = locals;
+        self.ready = ready;
+        self.eof = eof;
+    }
+}
 
 pub struct Pipe<S,P>
 {
@@ -316,6 +533,20 @@ where S: Source,
         self.source.processed()
     }
 }
+impl<S,P> Checkpoint for Pipe<S,P>
+where S: Checkpoint,
+      P: Checkpoint
+{
+    type State = (S::State,P::State);
+
+    fn checkpoint(&self) -> Option<(S::State,P::State)> {
+        Some((self.source.checkpoint()?,self.parser.checkpoint()?))
+    }
+    fn restore(&mut self, (source,parser): (S::State,P::State)) {
+        self.source.restore(source);
+        self.parser.restore(parser);
+    }
+}
 
 pub struct Filtered<S,F> {
     source: S,
@@ -346,18 +577,38 @@ where S: Source,
         self.source.processed()
     }
 }
+impl<S,F> Checkpoint for Filtered<S,F>
+where S: Checkpoint
+{
+    type State = S::State;
+
+    fn checkpoint(&self) -> Option<S::State> {
+        self.source.checkpoint()
+    }
+    fn restore(&mut self, state: S::State) {
+        self.source.restore(state);
+    }
+}
+
+/*
+
+   Offset source: all Locals (and processed()) are shifted by the offset,
+   a fragment of the parent document (an attribute value, a field of a record) gets parent coordinates:
+
+      let offset = Processed::offset_in(parent,fragment).unwrap();
+      fragment.into_source().with_offset(offset)
+
+*/
 
-struct Shift<S> {
+pub struct Shift<S> {
     source: S,
-    char_offset: usize,
-    byte_offset: usize,
+    shift: Processed,
 }
 impl<S> Shift<S> {
-    fn new(source: S, shift: Processed) -> Shift<S> {
+    pub fn new(source: S, shift: Processed) -> Shift<S> {
         Shift {
             source,
-            char_offset: shift.chars,
-            byte_offset: shift.bytes,
+            shift,
         }
     }
 }
@@ -365,18 +616,31 @@ impl<S> Source for  Shift<S>
 where S: Source
 {
     fn next_char(&mut self) -> SourceResult {
-        Ok(match self.source.next_char()? {
-            Some(ev) => Some(ev.with_shift(self.char_offset,self.byte_offset)),
+        Ok(match self.source.next_char().map_err(|e| e.with_shift(self.shift))? {
+            Some(ev) => Some(ev.with_offset(self.shift)),
             None => None,
         })
     }
     fn processed(&self) -> Processed {
         let mut p = self.source.processed();
-        p.chars += self.char_offset;
-        p.bytes += self.byte_offset;
+        p.chars += self.shift.chars;
+        p.bytes += self.shift.bytes;
+        p.utf16 += self.shift.utf16;
         p
     }
 }
+impl<S> Checkpoint for Shift<S>
+where S: Checkpoint
+{
+    type State = S::State;
+
+    fn checkpoint(&self) -> Option<S::State> {
+        self.source.checkpoint()
+    }
+    fn restore(&mut self, state: S::State) {
+        self.source.restore(state);
+    }
+}
 
 enum InnerChain<S1,S2> {
     First(S1),
@@ -436,77 +700,238 @@ use unicode_properties::{
       Zl      => Breaker::Line                   
       Zp      => Breaker::Paragraph
 
+   Overrides (Classifier): per-char rules before crlf before per-category rules,
+   for the same char (category) the last call wins:
+
+      with_char(c,..)         per-char, None keeps the char
+      with_crlf               bare \r and \r\n are one Breaker::Line
+      with_category(cat,..)   per-category (for chars without a per-char rule)
+
+      Classifier::new()
+          .with_char('\u{A0}',None)                        // NBSP stays a char
+          .with_crlf()
+          .with_char('\u{C}',Some(Breaker::Section))       // form feed is a page break
+          .with_char('\u{200B}',Some(Breaker::None))       // zero-width space
+
    Merging Breakers:
       
-      1) Breaker::Line + Breaker::Line = Breaker::Paragraph
+      1) Breaker::Line + Breaker::Line = Breaker::Paragraph (MergePolicy::with_lines_to_paragraph(2))
       2) Merge by "Inclusiveness": Sentence = sentence breaker + word breaker, etc.
 
 */
 
+#[derive(Debug,Clone,Default)]
+pub struct Classifier {
+    chars: Vec<(char,Option<Breaker>)>,
+    categories: Vec<(GeneralCategory,Option<Breaker>)>,
+    crlf: bool,
+}
+impl Classifier {
+    pub fn new() -> Classifier {
+        Classifier::default()
+    }
+    pub fn with_char(mut self, c: char, breaker: Option<Breaker>) -> Classifier {
+        self.chars.insert(0,(c,breaker));
+        self
+    }
+    pub fn with_category(mut self, category: GeneralCategory, breaker: Option<Breaker>) -> Classifier {
+        self.categories.insert(0,(category,breaker));
+        self
+    }
+    pub fn with_crlf(mut self) -> Classifier {
+        self.crlf = true;
+        self
+    }
+
+    pub fn classify(&self, c: char) -> Option<Breaker> {
+        if let Some((_,breaker)) = self.chars.iter().find(|(ch,_)| *ch == c) {
+            return *breaker;
+        }
+        match c {
+            '\n' => return Some(Breaker::Line),
+            '\r' if self.crlf => return Some(Breaker::Line),
+            _ => {},
+        }
+        let category = c.general_category();
+        if let Some((_,breaker)) = self.categories.iter().find(|(cat,_)| *cat == category) {
+            return *breaker;
+        }
+        match category {
+            GeneralCategory::Control |
+            GeneralCategory::SpaceSeparator => Some(Breaker::Space),
+            GeneralCategory::LineSeparator => Some(Breaker::Line),                    
+            GeneralCategory::ParagraphSeparator => Some(Breaker::Paragraph),
+            _ => None,
+        }
+    }
+    fn source_event(&self, se: SourceEvent) -> SourceEvent {
+        match se {
+            SourceEvent::Char(c) => match self.classify(c) {
+                Some(breaker) => SourceEvent::Breaker(breaker),
+                None => SourceEvent::Char(c),
+            },
+            b @ SourceEvent::Breaker(..) => b,
+        }
+    }
+}
+
 pub struct IntoSeparator<S> {
     source: S,
+    classifier: Classifier,
+    pending: Option<Local<SourceEvent>>, // event after '\r' (crlf)
 }
 impl<S> Source for IntoSeparator<S>
 where S: Source
 {
     fn next_char(&mut self) -> SourceResult {
-        self.source.next_char().map(|opt_lse| {
-            opt_lse.map(|local_se| {
-                local_se.map(|se| {
-                    match se {
-                        SourceEvent::Char(c) => {
-                            match c {
-                                '\n' => SourceEvent::Breaker(Breaker::Line),
-                                _ => match c.general_category() {
-                                    GeneralCategory::Control |
-                                    GeneralCategory::SpaceSeparator => SourceEvent::Breaker(Breaker::Space),
-                                    GeneralCategory::LineSeparator => SourceEvent::Breaker(Breaker::Line),                    
-                                    GeneralCategory::ParagraphSeparator => SourceEvent::Breaker(Breaker::Paragraph),
-                                    _ => SourceEvent::Char(c),
-                                },
-                            }
-                        },
-                        b @ SourceEvent::Breaker(..) => b,
-                    }
-                })
-            })
-        })
+        let local_se = match self.pending.take() {
+            Some(local_se) => local_se,
+            None => match self.source.next_char()? {
+                Some(local_se) => local_se,
+                None => return Ok(None),
+            },
+        };
+        if self.classifier.crlf && (*local_se.data() == SourceEvent::Char('\r')) {
+            match self.source.next_char() {
+                Ok(Some(next)) => match *next.data() == SourceEvent::Char('\n') {
+                    true => return Ok(Some(Local::from_segment(local_se,next)?.with_inner(SourceEvent::Breaker(Breaker::Line)))),
+                    false => self.pending = Some(next),
+                },
+                Ok(None) => {},
+                Err(e) => {
+                    // '\r' is classified again after Error::Pending
+                    self.pending = Some(local_se);
+                    return Err(e);
+                },
+            }
+        }
+        Ok(Some(local_se.map(|se| self.classifier.source_event(se))))
     }
     fn processed(&self) -> Processed {
         self.source.processed()
     }
 }
+impl<S> Checkpoint for IntoSeparator<S>
+where S: Checkpoint
+{
+    type State = (S::State,Option<Local<SourceEvent>>);
+
+    fn checkpoint(&self) -> Option<Self::State> {
+        Some((self.source.checkpoint()?,self.pending))
+    }
+    fn restore(&mut self, (source,pending): Self::State) {
+        self.source.restore(source);
+        self.pending = pending;
+    }
+}
+
+
+/*
+
+   MergePolicy: by default consecutive breakers are merged into the strongest one ("Inclusiveness").
+
+      with_lines_to_paragraph(n): n (or more) Line breakers in a merged run give a Paragraph
+      with_distinct_none:         Breaker::None is never merged, it is kept as a distinct (zero-width) event
+      with_section_bounds:        Section is never merged with neighbour breakers
+      with_max_length(chars):     merged breaker is never longer than `chars`, a longer run is split
 
+*/
+
+#[derive(Debug,Clone,Copy,Default)]
+pub struct MergePolicy {
+    lines_to_paragraph: Option<usize>,
+    distinct_none: bool,
+    section_bounds: bool,
+    max_length: Option<usize>,
+}
+impl MergePolicy {
+    pub fn new() -> MergePolicy {
+        MergePolicy::default()
+    }
+    pub fn with_lines_to_paragraph(mut self, lines: usize) -> MergePolicy {
+        self.lines_to_paragraph = Some(lines);
+        self
+    }
+    pub fn with_distinct_none(mut self) -> MergePolicy {
+        self.distinct_none = true;
+        self
+    }
+    pub fn with_section_bounds(mut self) -> MergePolicy {
+        self.section_bounds = true;
+        self
+    }
+    pub fn with_max_length(mut self, chars: usize) -> MergePolicy {
+        self.max_length = Some(chars);
+        self
+    }
+
+    fn lines(&self, mut merged: Merged) -> Merged {
+        if let Some(lines) = self.lines_to_paragraph && (merged.lines >= lines) {
+            merged.breaker = strongest(merged.breaker,Breaker::Paragraph);
+        }
+        merged
+    }
+    fn start(&self, local: Local<()>, breaker: Breaker) -> Merged {
+        self.lines(Merged { local, breaker, lines: (breaker == Breaker::Line) as usize })
+    }
+    // None if breakers can't be merged
+    fn merge(&self, current: &Merged, local: Local<()>, breaker: Breaker) -> Result<Option<Merged>,Error> {
+        if self.section_bounds && ((current.breaker == Breaker::Section) || (breaker == Breaker::Section)) {
+            return Ok(None);
+        }
+        let local = Local::from_segment(current.local,local)?;
+        if let Some(max) = self.max_length && (local.chars().length > max) {
+            return Ok(None);
+        }
+        Ok(Some(self.lines(Merged {
+            local,
+            breaker: strongest(current.breaker,breaker),
+            lines: current.lines + (breaker == Breaker::Line) as usize,
+        })))
+    }
+}
+
+fn strongest(cur_b: Breaker, nxt_b: Breaker) -> Breaker {
+    match (cur_b,nxt_b) {
+        (Breaker::None,_) => nxt_b,
+        (_,Breaker::None) => cur_b,
+        (Breaker::Space,_) => nxt_b,
+        (_,Breaker::Space) => cur_b,
+        (Breaker::Word,_) => nxt_b,
+        (_,Breaker::Word) => cur_b,
+        (Breaker::Line,_) => nxt_b,
+        (_,Breaker::Line) => cur_b,
+        (Breaker::Sentence,_) => nxt_b,
+        (_,Breaker::Sentence) => cur_b,                
+        (Breaker::Paragraph,_) => nxt_b,
+        (_,Breaker::Paragraph) => cur_b,
+        (Breaker::Section,Breaker::Section) => nxt_b,
+    }
+}
+
+#[derive(Debug,Clone,Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Merged {
+    local: Local<()>,
+    breaker: Breaker,
+    lines: usize,
+}
+impl Merged {
+    fn into_event(self) -> Local<SourceEvent> {
+        self.local.with_inner(SourceEvent::Breaker(self.breaker))
+    }
+}
 
 pub struct MergeSeparator<S> {
     source: S,
+    policy: MergePolicy,
     buffer: Option<Local<SourceEvent>>,
-    current: Option<(Local<()>,Breaker)>,
+    current: Option<Merged>,
 }
 impl<S> Source for MergeSeparator<S>
 where S: Source
 {
     fn next_char(&mut self) -> SourceResult {
-        fn merge_breakers(cur_loc: Local<()>, cur_b: Breaker, nxt_loc: Local<()>, nxt_b: Breaker) -> Result<(Local<()>,Breaker),Error> {
-            let loc = Local::from_segment(cur_loc,nxt_loc)?;
-            Ok((loc,match (cur_b,nxt_b) {
-                (Breaker::None,_) => nxt_b,
-                (_,Breaker::None) => cur_b,
-                (Breaker::Space,_) => nxt_b,
-                (_,Breaker::Space) => cur_b,
-                (Breaker::Word,_) => nxt_b,
-                (_,Breaker::Word) => cur_b,
-                (Breaker::Line,_) => nxt_b,
-                (_,Breaker::Line) => cur_b,
-                (Breaker::Sentence,_) => nxt_b,
-                (_,Breaker::Sentence) => cur_b,                
-                (Breaker::Paragraph,_) => nxt_b,
-                (_,Breaker::Paragraph) => cur_b,
-                (Breaker::Section,Breaker::Section) => nxt_b,
-            }))
-        }
-
-        
         loop {
             match self.buffer.take() {
                 Some(lse) => break Ok(Some(lse)),
@@ -516,24 +941,35 @@ where S: Source
                             let (local,se) = local_se.into_inner();
                             match se {
                                 c @ SourceEvent::Char(..) => match self.current.take() {
-                                    Some((local_br,br)) => {
+                                    Some(merged) => {
                                         self.buffer = Some(local.with_inner(c));
-                                        break Ok(Some(local_br.with_inner(SourceEvent::Breaker(br))));
+                                        break Ok(Some(merged.into_event()));
                                     },
                                     None => break Ok(Some(local.with_inner(c))),
                                 },
+                                b @ SourceEvent::Breaker(Breaker::None) if self.policy.distinct_none => match self.current.take() {
+                                    Some(merged) => {
+                                        self.buffer = Some(local.with_inner(b));
+                                        break Ok(Some(merged.into_event()));
+                                    },
+                                    None => break Ok(Some(local.with_inner(b))),
+                                },
                                 SourceEvent::Breaker(br) => match self.current.take() {
-                                    Some((c_local,c_br)) => {
-                                        self.current = Some(merge_breakers(c_local,c_br,local,br)?);
+                                    Some(merged) => match self.policy.merge(&merged,local,br)? {
+                                        Some(merged) => self.current = Some(merged),
+                                        None => {
+                                            self.current = Some(self.policy.start(local,br));
+                                            break Ok(Some(merged.into_event()));
+                                        },
                                     },
                                     None => {
-                                        self.current = Some((local,br));
+                                        self.current = Some(self.policy.start(local,br));
                                     },
                                 },
                             }
                         },
                         None => match self.current.take() {
-                            Some((local,br)) => break Ok(Some(local.with_inner(SourceEvent::Breaker(br)))),
+                            Some(merged) => break Ok(Some(merged.into_event())),
                             None => break Ok(None),
                         },
                     }
@@ -545,6 +981,20 @@ where S: Source
         self.source.processed()
     }
 }
+impl<S> Checkpoint for MergeSeparator<S>
+where S: Checkpoint
+{
+    type State = (S::State,Option<Local<SourceEvent>>,Option<Merged>);
+
+    fn checkpoint(&self) -> Option<Self::State> {
+        Some((self.source.checkpoint()?,self.buffer,self.current))
+    }
+    fn restore(&mut self, (source,buffer,current): Self::State) {
+        self.source.restore(source);
+        self.buffer = buffer;
+        self.current = current;
+    }
+}
 
 
 #[cfg(test)]
@@ -694,6 +1144,134 @@ mod tests {
     }
 
 
+    fn merged<S: Source>(mut src: S) -> Vec<Local<SourceEvent>> {
+        let mut res = Vec::new();
+        while let Some(local_event) = src.next_char().unwrap() {
+            res.push(local_event);
+        }
+        res
+    }
+
+    fn ascii(se: SourceEvent, offset: usize, length: usize) -> Local<SourceEvent> {
+        se.localize(Snip { offset, length },Snip { offset, length })
+    }
+
+    #[test]
+    fn merge_policy_lines() {
+        let text = "a\n \nb\n c";
+        let res = merged(text.into_source().into_separator().merge_separators());
+        assert_eq!(res,vec![
+            ascii(SourceEvent::Char('a'),0,1),
+            ascii(SourceEvent::Breaker(Breaker::Line),1,3),
+            ascii(SourceEvent::Char('b'),4,1),
+            ascii(SourceEvent::Breaker(Breaker::Line),5,2),
+            ascii(SourceEvent::Char('c'),7,1),
+        ]);
+
+        let policy = MergePolicy::new().with_lines_to_paragraph(2);
+        let res = merged(text.into_source().into_separator().merge_separators_with(policy));
+        assert_eq!(res,vec![
+            ascii(SourceEvent::Char('a'),0,1),
+            ascii(SourceEvent::Breaker(Breaker::Paragraph),1,3),
+            ascii(SourceEvent::Char('b'),4,1),
+            ascii(SourceEvent::Breaker(Breaker::Line),5,2),
+            ascii(SourceEvent::Char('c'),7,1),
+        ]);
+
+        let policy = MergePolicy::new().with_max_length(2);
+        let res = merged("a     b".into_source().into_separator().merge_separators_with(policy));
+        assert_eq!(res,vec![
+            ascii(SourceEvent::Char('a'),0,1),
+            ascii(SourceEvent::Breaker(Breaker::Space),1,2),
+            ascii(SourceEvent::Breaker(Breaker::Space),3,2),
+            ascii(SourceEvent::Breaker(Breaker::Space),5,1),
+            ascii(SourceEvent::Char('b'),6,1),
+        ]);
+    }
+
+    #[test]
+    fn merge_policy_bounds() {
+        let source = || "a ".into_source()
+            .chain(Breaker::Section.into_source_as("\n"))
+            .chain(Breaker::None.into_source_as(""))
+            .chain(" b".into_source())
+            .into_separator();
+
+        let res = merged(source().merge_separators());
+        assert_eq!(res,vec![
+            ascii(SourceEvent::Char('a'),0,1),
+            ascii(SourceEvent::Breaker(Breaker::Section),1,3),
+            ascii(SourceEvent::Char('b'),4,1),
+        ]);
+
+        let policy = MergePolicy::new().with_section_bounds().with_distinct_none();
+        let res = merged(source().merge_separators_with(policy));
+        assert_eq!(res,vec![
+            ascii(SourceEvent::Char('a'),0,1),
+            ascii(SourceEvent::Breaker(Breaker::Space),1,1),
+            ascii(SourceEvent::Breaker(Breaker::Section),2,1),
+            ascii(SourceEvent::Breaker(Breaker::None),3,0),
+            ascii(SourceEvent::Breaker(Breaker::Space),3,1),
+            ascii(SourceEvent::Char('b'),4,1),
+        ]);
+    }
+
+    #[test]
+    fn classifier() {
+        fn separated<S: Source>(src: S) -> Vec<(SourceEvent,usize,usize)> {
+            merged(src).into_iter().map(|lse| (*lse.data(),lse.chars().offset,lse.chars().length)).collect()
+        }
+        let text = "a\u{A0}b\r\nc\rd\u{C}e\u{200B}f ";
+
+        assert_eq!(separated(text.into_source().into_separator()),vec![
+            (SourceEvent::Char('a'),0,1),
+            (SourceEvent::Breaker(Breaker::Space),1,1),
+            (SourceEvent::Char('b'),2,1),
+            (SourceEvent::Breaker(Breaker::Space),3,1),
+            (SourceEvent::Breaker(Breaker::Line),4,1),
+            (SourceEvent::Char('c'),5,1),
+            (SourceEvent::Breaker(Breaker::Space),6,1),
+            (SourceEvent::Char('d'),7,1),
+            (SourceEvent::Breaker(Breaker::Space),8,1),
+            (SourceEvent::Char('e'),9,1),
+            (SourceEvent::Char('\u{200B}'),10,1),
+            (SourceEvent::Char('f'),11,1),
+            (SourceEvent::Breaker(Breaker::Space),12,1),
+        ]);
+
+        let classifier = Classifier::new()
+            .with_char('\u{A0}',None)
+            .with_crlf()
+            .with_char('\u{C}',Some(Breaker::Section))
+            .with_category(GeneralCategory::Format,Some(Breaker::None));
+        assert_eq!(separated(text.into_source().into_separator_with(classifier)),vec![
+            (SourceEvent::Char('a'),0,1),
+            (SourceEvent::Char('\u{A0}'),1,1),
+            (SourceEvent::Char('b'),2,1),
+            (SourceEvent::Breaker(Breaker::Line),3,2),
+            (SourceEvent::Char('c'),5,1),
+            (SourceEvent::Breaker(Breaker::Line),6,1),
+            (SourceEvent::Char('d'),7,1),
+            (SourceEvent::Breaker(Breaker::Section),8,1),
+            (SourceEvent::Char('e'),9,1),
+            (SourceEvent::Breaker(Breaker::None),10,1),
+            (SourceEvent::Char('f'),11,1),
+            (SourceEvent::Breaker(Breaker::Space),12,1),
+        ]);
+
+        // the last call wins, per-char rules before per-category ones
+        let classifier = Classifier::new()
+            .with_char('\u{A0}',Some(Breaker::Sentence))
+            .with_char('\u{A0}',None)
+            .with_category(GeneralCategory::SpaceSeparator,Some(Breaker::Word))
+            .with_category(GeneralCategory::SpaceSeparator,Some(Breaker::Line))
+            .with_char('\r',Some(Breaker::Paragraph))
+            .with_crlf();
+        assert_eq!(classifier.classify('\u{A0}'),None);
+        assert_eq!(classifier.classify(' '),Some(Breaker::Line));
+        assert_eq!(classifier.classify('\r'),Some(Breaker::Paragraph));
+    }
+
     #[test]
     fn chain_1() {
         let src = " &GreaterGreater; &#x09;\n &#128175; &#xFEFF;";
@@ -741,4 +1319,118 @@ mod tests {
         }
     }
 
+    #[test]
+    fn utf16_chain() {
+        let mut src = "💯&amp;x".into_source()
+            .chain("😀y".into_source())
+            .pipe(crate::entities::Builder::new().create().into_piped());
+
+        let res = vec![
+            SourceEvent::Char('💯').localize_utf16(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 4 },Snip { offset: 0, length: 2 }),
+            SourceEvent::Char('&').localize_utf16(Snip { offset: 1, length: 5 },Snip { offset: 4, length: 5 },Snip { offset: 2, length: 5 }),
+            SourceEvent::Char('x').localize_utf16(Snip { offset: 6, length: 1 },Snip { offset: 9, length: 1 },Snip { offset: 7, length: 1 }),
+            SourceEvent::Char('😀').localize_utf16(Snip { offset: 7, length: 1 },Snip { offset: 10, length: 4 },Snip { offset: 8, length: 2 }),
+            SourceEvent::Char('y').localize_utf16(Snip { offset: 8, length: 1 },Snip { offset: 14, length: 1 },Snip { offset: 10, length: 1 }),
+        ];
+        let mut res_iter = res.clone().into_iter();
+
+        while let Some(local_event) = src.next_char().unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Source: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+        assert_eq!(src.processed(),Processed { chars: 9, bytes: 15, utf16: 11 });
+
+        let segment = Local::from_segment(res[1],res[4]).unwrap();
+        assert_eq!(segment.utf16(),Snip { offset: 2, length: 9 });
+    }
+
+    #[test]
+    fn flat_map() {
+        let mut src = "Straße æ a\u{301}\u{AD}a"
+            .into_source()
+            .flat_map(|pending: &[SourceEvent], eof: bool| match pending {
+                [SourceEvent::Char('ß'), ..] => Mapped::Emit { consumed: 1, events: OptVec::Two([SourceEvent::Char('s'),SourceEvent::Char('s')]) },
+                [SourceEvent::Char('æ'), ..] => Mapped::Emit { consumed: 1, events: OptVec::Two([SourceEvent::Char('a'),SourceEvent::Char('e')]) },
+                [SourceEvent::Char('\u{AD}'), ..] => Mapped::Emit { consumed: 1, events: OptVec::None },
+                [SourceEvent::Char('a')] if !eof => Mapped::Wait,
+                [SourceEvent::Char('a'), SourceEvent::Char('\u{301}'), ..] => Mapped::Emit { consumed: 2, events: OptVec::One(SourceEvent::Char('á')) },
+                _ => Mapped::Emit { consumed: 1, events: OptVec::One(pending[0]) },
+            })
+            .into_separator();
+
+        let mut res_iter = [
+            SourceEvent::Char('S').localize(Snip { offset: 0, length: 1 },Snip { offset: 0, length: 1 }),
+            SourceEvent::Char('t').localize(Snip { offset: 1, length: 1 },Snip { offset: 1, length: 1 }),
+            SourceEvent::Char('r').localize(Snip { offset: 2, length: 1 },Snip { offset: 2, length: 1 }),
+            SourceEvent::Char('a').localize(Snip { offset: 3, length: 1 },Snip { offset: 3, length: 1 }),
+            SourceEvent::Char('s').localize(Snip { offset: 4, length: 1 },Snip { offset: 4, length: 2 }),
+            SourceEvent::Char('s').localize(Snip { offset: 4, length: 1 },Snip { offset: 4, length: 2 }),
+            SourceEvent::Char('e').localize(Snip { offset: 5, length: 1 },Snip { offset: 6, length: 1 }),
+            SourceEvent::Breaker(Breaker::Space).localize(Snip { offset: 6, length: 1 },Snip { offset: 7, length: 1 }),
+            SourceEvent::Char('a').localize(Snip { offset: 7, length: 1 },Snip { offset: 8, length: 2 }),
+            SourceEvent::Char('e').localize(Snip { offset: 7, length: 1 },Snip { offset: 8, length: 2 }),
+            SourceEvent::Breaker(Breaker::Space).localize(Snip { offset: 8, length: 1 },Snip { offset: 10, length: 1 }),
+            SourceEvent::Char('á').localize(Snip { offset: 9, length: 2 },Snip { offset: 11, length: 3 }),
+            SourceEvent::Char('a').localize(Snip { offset: 12, length: 1 },Snip { offset: 16, length: 1 }),
+        ].into_iter();
+
+        while let Some(local_event) = src.next_char().unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Source: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+    }
+
+    #[test]
+    fn with_offset() {
+        let parent = "💯 <a title=\"&amp;é\">";
+        let begin = parent.find("&amp;").unwrap();
+        let value = &parent[begin .. begin + 7];
+        let offset = Processed::offset_in(parent,value).unwrap();
+        assert_eq!(offset,Processed { chars: 12, bytes: 15, utf16: 13 });
+        let copy = value.to_string();
+        assert_eq!(Processed::offset_in(parent,&copy),None);
+
+        let mut src = value.into_source()
+            .with_offset(offset)
+            .pipe(crate::entities::Builder::new().create().into_piped());
+
+        let mut res_iter = [
+            SourceEvent::Char('&').localize_utf16(Snip { offset: 12, length: 5 },Snip { offset: 15, length: 5 },Snip { offset: 13, length: 5 }),
+            SourceEvent::Char('é').localize_utf16(Snip { offset: 17, length: 1 },Snip { offset: 20, length: 2 },Snip { offset: 18, length: 1 }),
+        ].into_iter();
+
+        while let Some(local_event) = src.next_char().unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Source: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+        assert_eq!(res_iter.next(),None);
+        assert_eq!(src.processed(),Processed { chars: 18, bytes: 22, utf16: 19 });
+        assert_eq!(src.processed(),Processed::of(&parent[.. begin + 7]));
+    }
 }
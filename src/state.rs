@@ -6,6 +6,7 @@ use crate::{
     Parser, 
     ParserResult,
     Source, SourceEvent,
+    Checkpoint,
 };
 
 use opt_struct::OptVec;
@@ -115,6 +116,11 @@ where S: StateMachine<Data = D, Context = C>
                         if let Some(local) = match src.next_char() {
                             Ok(None) => self.process_eof(inner_state.eof(&self.context)),                            
                             Ok(Some(local_char)) => self.process(inner_state.next_state(local_char,&self.context)),
+                            Err(Error::Pending) => {
+                                // no input yet: state is kept, parsing can be resumed
+                                self.state = State::Inner(inner_state);
+                                Err(Error::Pending)
+                            },
                             Err(e) => self.process_err(e),
                         }? {
                             break Ok(Some(local))
@@ -125,3 +131,32 @@ where S: StateMachine<Data = D, Context = C>
         }
     }
 }
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct RuntimeState<S,D> {
+    state: S,
+    buffer: Vec<Local<ParserEvent<D>>>,
+}
+
+impl<S,D,C> Checkpoint for Runtime<S,D,C>
+where S: StateMachine<Data = D, Context = C> + Clone,
+      D: Clone
+{
+    type State = RuntimeState<S,D>;
+
+    fn checkpoint(&self) -> Option<RuntimeState<S,D>> {
+        match &self.state {
+            State::Inner(state) => Some(RuntimeState {
+                state: state.clone(),
+                buffer: self.buffer.iter().cloned().collect(),
+            }),
+            State::SourceDone |
+            State::SourceInvalid => None,
+        }
+    }
+    fn restore(&mut self, state: RuntimeState<S,D>) {
+        self.state = State::Inner(state.state);
+        self.buffer = state.buffer.into();
+    }
+}
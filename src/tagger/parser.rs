@@ -1,6 +1,6 @@
 use super::{
     tags::{
-        Tag, TagName,
+        Tag, TagName, Closing,
     },
     state::{
         TaggerState,
@@ -13,7 +13,8 @@ use crate::{
     Local,
     ParserResult,
     Source,
-    Parser, Runtime,
+    Parser, Runtime, RuntimeState,
+    Checkpoint, Diagnostics, Warning,
 };
 
 /*
@@ -69,21 +70,34 @@ impl Builder {
         self.properties.eof_to_named_tag = true;
         self
     }
+    // Error::LimitExceeded if a tag is longer (in source events), unlimited by default
+    pub fn with_max_tag_length(mut self, limit: usize) -> Builder {
+        self.properties.max_tag_length = Some(limit);
+        self
+    }
+    // stray '<', unclosed attribute quotes and mismatched close tags are reported into the sink
+    pub fn with_diagnostics(mut self, diagnostics: Diagnostics) -> Builder {
+        self.properties.diagnostics = Some(diagnostics);
+        self
+    }
     pub fn create(self) -> TagParser {
-        match self.auto_detect {
-            false => TagParser(InnerTagParser::Xhtml(XhtmlParser {
+        let open = self.properties.diagnostics.clone().map(|diagnostics| OpenTags { diagnostics, stack: Vec::new() });
+        let inner = match self.auto_detect {
+            false => InnerTagParser::Xhtml(XhtmlParser {
                 done: false,
                 eof_in_tag: self.eof_in_tag,
                 sbuffer: None,
                 pbuffer: None,
                 runtime: Runtime::new(self.properties),
                 final_error: None,
-            })),
-            true => TagParser(InnerTagParser::Detector(Detector{
+            }),
+            true => InnerTagParser::Detector(Detector{
                 eof_in_tag: self.eof_in_tag,
                 runtime: Runtime::new(self.properties),
-            })),
-        }
+                detecting: None,
+            }),
+        };
+        TagParser(inner,open)
     }
 }
 
@@ -105,22 +119,161 @@ pub(in super) enum AttributeProperties {
 pub(in super) struct TaggerProperties {
     pub attributes: AttributeProperties,
     pub eof_to_named_tag: bool,
+    pub max_tag_length: Option<usize>,
+    pub diagnostics: Option<Diagnostics>,
 }
 impl Default for TaggerProperties {
     fn default() -> TaggerProperties {
         TaggerProperties {
             attributes: AttributeProperties::None,
             eof_to_named_tag: false,
+            max_tag_length: None,
+            diagnostics: None,
         }
     }
 }
 
-pub struct TagParser(InnerTagParser);
+pub struct TagParser(InnerTagParser,Option<OpenTags>);
 impl Parser for TagParser {
     type Data = Tag;
     
     fn next_event<S: Source>(&mut self, src: &mut S) -> ParserResult<Tag> {
-        self.0.next_event(src)
+        let res = self.0.next_event(src);
+        if let (Ok(Some(lpe)),Some(open)) = (&res,&mut self.1) {
+            open.check(lpe);
+        }
+        res
+    }
+}
+
+// open elements, tracked only with diagnostics
+struct OpenTags {
+    diagnostics: Diagnostics,
+    stack: Vec<TagName>,
+}
+impl OpenTags {
+    fn check(&mut self, lpe: &Local<ParserEvent<Tag>>) {
+        let tag = match lpe.data() {
+            ParserEvent::Parsed(tag) => tag,
+            _ => return,
+        };
+        match tag.closing {
+            Closing::Void => {},
+            Closing::Open => self.stack.push(tag.name.clone()),
+            Closing::Close => match self.stack.iter().rposition(|name| *name == tag.name) {
+                Some(idx) => {
+                    if self.stack[idx+1 ..].iter().any(|name| !name.is_optional_end()) {
+                        self.diagnostics.push(lpe.local(()),Warning::MismatchedCloseTag(tag.name.clone()));
+                    }
+                    self.stack.truncate(idx);
+                },
+                None => self.diagnostics.push(lpe.local(()),Warning::MismatchedCloseTag(tag.name.clone())),
+            },
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct TagCheckpoint(InnerCheckpoint,Vec<TagName>);
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+enum InnerCheckpoint {
+    Detector {
+        runtime: RuntimeState<TaggerState,Tag>,
+        detecting: Option<(Counter,Vec<Local<ParserEvent<Tag>>>)>,
+    },
+    Xhtml {
+        sbuffer: Vec<Local<SourceEvent>>,
+        pbuffer: Vec<Local<ParserEvent<Tag>>>,
+        runtime: RuntimeState<TaggerState,Tag>,
+    },
+    Plain {
+        sbuffer: Vec<Local<SourceEvent>>,
+        pbuffer: Vec<Local<ParserEvent<Tag>>>,
+        runtime: RuntimeState<TaggerState,Tag>,
+    },
+}
+
+fn rest<T: Clone>(buffer: &Option<std::vec::IntoIter<T>>) -> Vec<T> {
+    match buffer {
+        Some(iter) => iter.as_slice().to_vec(),
+        None => Vec::new(),
+    }
+}
+
+impl Checkpoint for TagParser {
+    type State = TagCheckpoint;
+
+    fn checkpoint(&self) -> Option<TagCheckpoint> {
+        let open = match &self.1 {
+            Some(open) => open.stack.clone(),
+            None => Vec::new(),
+        };
+        Some(TagCheckpoint(match &self.0 {
+            InnerTagParser::None => return None,
+            InnerTagParser::Detector(detector) => InnerCheckpoint::Detector {
+                runtime: detector.runtime.checkpoint()?,
+                detecting: detector.detecting.clone(),
+            },
+            InnerTagParser::Xhtml(parser) => match parser.done {
+                true => return None,
+                false => InnerCheckpoint::Xhtml {
+                    sbuffer: rest(&parser.sbuffer),
+                    pbuffer: rest(&parser.pbuffer),
+                    runtime: parser.runtime.checkpoint()?,
+                },
+            },
+            InnerTagParser::Plain(parser) => match parser.done {
+                true => return None,
+                false => InnerCheckpoint::Plain {
+                    sbuffer: rest(&parser.sbuffer),
+                    pbuffer: rest(&parser.pbuffer),
+                    runtime: parser.runtime.checkpoint()?,
+                },
+            },
+        },open))
+    }
+    fn restore(&mut self, state: TagCheckpoint) {
+        // builder options (eof_in_tag, runtime context) are taken from the current parser
+        let (eof_in_tag,mut runtime) = match std::mem::replace(&mut self.0,InnerTagParser::None) {
+            InnerTagParser::None => return,
+            InnerTagParser::Detector(detector) => (detector.eof_in_tag,detector.runtime),
+            InnerTagParser::Xhtml(parser) => (parser.eof_in_tag,parser.runtime),
+            InnerTagParser::Plain(parser) => (parser.eof_in_tag,parser.runtime),
+        };
+        if let Some(open) = &mut self.1 {
+            open.stack = state.1;
+        }
+        self.0 = match state.0 {
+            InnerCheckpoint::Detector { runtime: state, detecting } => {
+                runtime.restore(state);
+                InnerTagParser::Detector(Detector { eof_in_tag, runtime, detecting })
+            },
+            InnerCheckpoint::Xhtml { sbuffer, pbuffer, runtime: state } => {
+                runtime.restore(state);
+                InnerTagParser::Xhtml(XhtmlParser {
+                    done: false,
+                    eof_in_tag,
+                    sbuffer: Some(sbuffer.into_iter()),
+                    pbuffer: Some(pbuffer.into_iter()),
+                    runtime,
+                    final_error: None,
+                })
+            },
+            InnerCheckpoint::Plain { sbuffer, pbuffer, runtime: state } => {
+                runtime.restore(state);
+                InnerTagParser::Plain(PlainParser {
+                    done: false,
+                    eof_in_tag,
+                    sbuffer: Some(sbuffer.into_iter()),
+                    pbuffer: Some(pbuffer.into_iter()),
+                    runtime,
+                    final_error: None,
+                })
+            },
+        };
     }
 }
 
@@ -164,7 +317,8 @@ impl Parser for InnerTagParser {
     }
 }
 
-#[derive(Default)]
+#[derive(Debug,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 struct Counter {
     common: usize,
     named: usize,
@@ -191,6 +345,7 @@ impl Counter {
 struct Detector {
     eof_in_tag: Unknown,
     runtime: Runtime<TaggerState,Tag,TaggerProperties>,
+    detecting: Option<(Counter,Vec<Local<ParserEvent<Tag>>>)>, // detection interrupted by Error::Pending
 }
 enum DetectorResult {
     Next(Detector,ParserResult<Tag>),
@@ -199,80 +354,22 @@ enum DetectorResult {
 }
 impl Detector {
     fn try_next<S: Source>(mut self, src: &mut S) -> DetectorResult {
+        if let Some((counter,pbuffer)) = self.detecting.take() {
+            return self.detect(src,counter,pbuffer);
+        }
         match self.runtime.next_event(src) {
             Ok(Some(lpe)) => match lpe.data() {
                 ParserEvent::Parsed(tag) => {
                     let mut counter = Counter::default();
                     counter.push(tag);                    
-                    let mut pbuffer = vec![lpe];
-                    let mut eof_buffer = None;
-                    let mut eof_error = None;
-                    let done = loop {
-                        match self.runtime.next_event(src) {
-                            Ok(Some(lpe)) => match lpe.data() {
-                                ParserEvent::Parsed(tag) => {
-                                    counter.push(tag);
-                                    pbuffer.push(lpe);
-                                },
-                                _ => pbuffer.push(lpe),
-                            },
-                            Ok(None) => break true,
-                            Err(Error::EofInTag(raw)) => {
-                                eof_buffer = Some(raw);
-                                break true;
-                            },
-                            Err(e) => {
-                                eof_error = Some(e);
-                                break true;
-                            }, 
-                        }
-
-                        // check detector
-                        if counter.check() { break false; }                        
-                    };
-                    // make decision, return parser
-                    match counter.tags() > 0 {
-                        true => {
-                            match (self.eof_in_tag, eof_buffer) {
-                                (_,None) |
-                                (Unknown::Skip,_) => {},
-                                (Unknown::Error,Some(raw)) => eof_error = Some(Error::EofInTag(raw)),
-                                (Unknown::Text,Some(raw)) => {
-                                    for lse in raw {
-                                        pbuffer.push(lse.map(|se| se.into()));
-                                    }
-                                }
-                            }
-                            DetectorResult::Xhtml(XhtmlParser {
-                                done,
-                                eof_in_tag: self.eof_in_tag,
-                                sbuffer: None,
-                                pbuffer: Some(pbuffer.into_iter()),
-                                runtime: self.runtime,
-                                final_error: eof_error,
-                            })
-                        },
-                        false => {
-                            if let Some(raw) = eof_buffer {
-                                for lse in raw {
-                                    pbuffer.push(lse.map(|se| se.into()));
-                                }
-                            }
-                            DetectorResult::Plain(PlainParser {
-                                done,
-                                sbuffer: None,
-                                pbuffer: Some(pbuffer.into_iter()),
-                                runtime: self.runtime,
-                                final_error: eof_error,
-                            })
-                        },
-                    }  
+                    self.detect(src,counter,vec![lpe])
                 },
                 _ => DetectorResult::Next(self,Ok(Some(lpe))),
             },
             Ok(None) => DetectorResult::Next(self,Ok(None)),                
-            Err(Error::EofInTag(raw)) => DetectorResult::Plain(PlainParser { // means no tags were found before
+            Err(Error::EofInTag { raw, .. }) => DetectorResult::Plain(PlainParser { // means no tags were found before
                 done: true,
+                eof_in_tag: self.eof_in_tag,
                 sbuffer: Some(raw.into_iter()),
                 pbuffer: None,
                 runtime: self.runtime,
@@ -281,11 +378,81 @@ impl Detector {
             Err(e) => DetectorResult::Next(self,Err(e)),             
         }
     }
+    fn detect<S: Source>(mut self, src: &mut S, mut counter: Counter, mut pbuffer: Vec<Local<ParserEvent<Tag>>>) -> DetectorResult {
+        let mut eof_buffer = None;
+        let mut eof_error = None;
+        let done = loop {
+            match self.runtime.next_event(src) {
+                Ok(Some(lpe)) => match lpe.data() {
+                    ParserEvent::Parsed(tag) => {
+                        counter.push(tag);
+                        pbuffer.push(lpe);
+                    },
+                    _ => pbuffer.push(lpe),
+                },
+                Ok(None) => break true,
+                Err(Error::EofInTag { raw, .. }) => {
+                    eof_buffer = Some(raw);
+                    break true;
+                },
+                Err(Error::Pending) => {
+                    self.detecting = Some((counter,pbuffer));
+                    return DetectorResult::Next(self,Err(Error::Pending));
+                },
+                Err(e) => {
+                    eof_error = Some(e);
+                    break true;
+                }, 
+            }
+
+            // check detector
+            if counter.check() { break false; }                        
+        };
+        // make decision, return parser
+        match counter.tags() > 0 {
+            true => {
+                match (self.eof_in_tag, eof_buffer) {
+                    (_,None) |
+                    (Unknown::Skip,_) => {},
+                    (Unknown::Error,Some(raw)) => eof_error = Some(Error::eof_in_tag(raw)),
+                    (Unknown::Text,Some(raw)) => {
+                        for lse in raw {
+                            pbuffer.push(lse.map(|se| se.into()));
+                        }
+                    }
+                }
+                DetectorResult::Xhtml(XhtmlParser {
+                    done,
+                    eof_in_tag: self.eof_in_tag,
+                    sbuffer: None,
+                    pbuffer: Some(pbuffer.into_iter()),
+                    runtime: self.runtime,
+                    final_error: eof_error,
+                })
+            },
+            false => {
+                if let Some(raw) = eof_buffer {
+                    for lse in raw {
+                        pbuffer.push(lse.map(|se| se.into()));
+                    }
+                }
+                DetectorResult::Plain(PlainParser {
+                    done,
+                    eof_in_tag: self.eof_in_tag,
+                    sbuffer: None,
+                    pbuffer: Some(pbuffer.into_iter()),
+                    runtime: self.runtime,
+                    final_error: eof_error,
+                })
+            },
+        }  
+    }
 }
 
 
 struct PlainParser {
     done: bool,
+    eof_in_tag: Unknown,
     sbuffer: Option<std::vec::IntoIter<Local<SourceEvent>>>,
     pbuffer: Option<std::vec::IntoIter<Local<ParserEvent<Tag>>>>,
     runtime: Runtime<TaggerState,Tag,TaggerProperties>,
@@ -357,7 +524,7 @@ impl Parser for PlainParser {
                     _ => Ok(Some(lpe)),
                 },
                 Ok(None) => self.final_err(),
-                Err(Error::EofInTag(raw)) => {
+                Err(Error::EofInTag { raw, .. }) => {
                     self.done = true;
                     let mut iter = raw.into_iter();
                     match iter.next() {
@@ -417,10 +584,10 @@ impl Parser for XhtmlParser {
                 match self.runtime.next_event(src) {
                     Ok(Some(lpe)) => break Ok(Some(lpe)),
                     Ok(None) => break self.final_err(),
-                    Err(Error::EofInTag(raw)) => {
+                    Err(Error::EofInTag { raw, .. }) => {
                         self.done = true;
                         match self.eof_in_tag {
-                            Unknown::Error => break Err(Error::EofInTag(raw)),
+                            Unknown::Error => break Err(Error::eof_in_tag(raw)),
                             Unknown::Skip => {},
                             Unknown::Text => {
                                 let mut iter = raw.into_iter();
@@ -2130,7 +2297,7 @@ mod tests {
         while let Some(local_event) = match parser.next_event(&mut src) {
             Ok(ope) => ope,
             Err(e) => match e {
-                Error::EofInTag(raw) => {
+                Error::EofInTag { raw, .. } => {
                     match raw.len() != eof.len() {
                         true => panic!("parser and eof_result differs in size"),
                         false => for (d,e) in raw.into_iter().zip(eof.iter()) {
@@ -2142,8 +2309,7 @@ mod tests {
                     
                     None
                 },
-                Error::EndBeforeBegin => panic!("{:?}",e),
-                Error::NoBegin => panic!("{:?}",e),
+                _ => panic!("{:?}",e),
             },
         } {
             /*if let ParserEvent::Parsed(tag) = local_event.data() {
@@ -14,10 +14,13 @@ use crate::{
     Error, Local, ParserEvent,
     NextResult, Next, StateMachine,
     SourceEvent, Breaker,
+    Warning,
+    diagnostics::warn,
 };
 
 
-#[derive(Debug)]
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub(in super) enum TaggerState {
     Init,
     MayBeTag{
@@ -50,8 +53,27 @@ impl Default for TaggerState {
         TaggerState::Init
     }
 }
+impl TaggerState {
+    fn raw(&self) -> Option<&Vec<Local<SourceEvent>>> {
+        match self {
+            TaggerState::Init => None,
+            TaggerState::MayBeTag{ raw, .. } |
+            TaggerState::SlashedTag{ raw, .. } |
+            TaggerState::TagName{ raw, .. } => Some(raw),
+            TaggerState::TagWaitAttrName(tag) |
+            TaggerState::TagWaitAttrEq(tag) |
+            TaggerState::TagWaitAttrValue(tag) |
+            TaggerState::TagAttrName(tag) |
+            TaggerState::TagAttrValue(tag) |
+            TaggerState::TagAttrValueApos(tag) |
+            TaggerState::TagAttrValueQuote(tag) |
+            TaggerState::TagEnd(tag) => Some(&tag.raw),
+        }
+    }
+}
 
 #[derive(Debug,Clone,Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub(in super) enum Kind {
     Open,
     Close,
@@ -60,7 +82,8 @@ pub(in super) enum Kind {
     Quest,
 }
 
-#[derive(Debug)]
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub(in super) struct ReadTag {
     begin: Local<char>,
     current: Local<char>,
@@ -71,9 +94,12 @@ pub(in super) struct ReadTag {
     tmp_buffer: Option<AttributeCollector>,
     raw: Vec<Local<SourceEvent>>,
 }
-#[derive(Debug)]
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 struct AttributeCollector {
+    #[cfg_attr(feature = "serde", serde(with = "crate::checkpoint::opt_vec"))]
     need: OptVec<String>, // None means all, if no attrs neede there is no this struct (tmp_buffer = None)
+    #[cfg_attr(feature = "serde", serde(with = "crate::checkpoint::opt_vec"))]
     attributes: OptVec<(String,Option<Snip>)>,
     tmp_name: String,
     tmp_value: Option<Snip>,
@@ -202,13 +228,16 @@ impl StateMachine for TaggerState {
         fn push_tag_eof(props: &TaggerProperties, tag: ReadTag)-> Result<Local<ParserEvent<Tag>>,Error> {
             match props.eof_to_named_tag && (tag.name.is_named() || tag.name.is_service()) {
                 true => create_tag_event(tag),
-                false => Err(Error::EofInTag(tag.raw)),
+                false => Err(Error::eof_in_tag(tag.raw)),
             }
         }
         
         Ok(match self {
             TaggerState::Init => Next::empty(),
-            TaggerState::MayBeTag{ tag_char, .. } => Next::empty().with_event(tag_char.map(|c| ParserEvent::Char(c))),
+            TaggerState::MayBeTag{ tag_char, .. } => {
+                warn(&props.diagnostics,tag_char.local(()),|| Warning::StrayLessThan);
+                Next::empty().with_event(tag_char.map(|c| ParserEvent::Char(c)))
+            },
             TaggerState::SlashedTag{ begin, current, .. } => {
                 Next::empty()
                     .with_event(begin.map(|c| ParserEvent::Char(c)))
@@ -224,18 +253,31 @@ impl StateMachine for TaggerState {
             TaggerState::TagWaitAttrEq(tag) |
             TaggerState::TagWaitAttrValue(tag) |
             TaggerState::TagAttrName(tag) |
-            TaggerState::TagAttrValue(tag) |
+            TaggerState::TagAttrValue(tag) => {
+                let lpe = push_tag_eof(props,tag)?;
+                Next::empty().with_event(lpe)
+            },
             TaggerState::TagAttrValueApos(tag) |
             TaggerState::TagAttrValueQuote(tag) => {
+                if let Ok(local) = Local::from_segment(tag.begin,tag.current) {
+                    warn(&props.diagnostics,local.local(()),|| Warning::UnclosedAttributeQuote);
+                }
                 let lpe = push_tag_eof(props,tag)?;
                 Next::empty().with_event(lpe)
             },
         })
     }
     fn next_state(self, local_src: Local<SourceEvent>, props: &TaggerProperties) -> NextResult<TaggerState,Tag> {
+        if let (Some(limit),Some(raw)) = (props.max_tag_length,self.raw()) && (raw.len() >= limit) {
+            let local = match raw.first() {
+                Some(first) => Local::from_segment(first.local(()),local_src)?,
+                None => local_src.local(()),
+            };
+            return Err(Error::LimitExceeded { local, limit });
+        }
         match self {
             TaggerState::Init => init(local_src),
-            TaggerState::MayBeTag{ tag_char, raw } => may_be_tag(tag_char,local_src,raw),
+            TaggerState::MayBeTag{ tag_char, raw } => may_be_tag(tag_char,local_src,raw,props),
             TaggerState::SlashedTag{ begin, current, raw } => slashed_tag(begin,current,local_src,raw),
             TaggerState::TagName{ begin, current, kind, name, raw } => tag_name(begin, current, local_src, kind, name, raw, props),
             TaggerState::TagWaitAttrName(tag) => tag_wait_attr_name(tag, local_src),
@@ -577,13 +619,15 @@ fn slashed_tag(begin: Local<char>, current: Local<char>, local_src: Local<Source
     })
 }
 
-fn may_be_tag(tag_char: Local<char>, local_src: Local<SourceEvent>, mut raw: Vec<Local<SourceEvent>>) -> NextResult<TaggerState,Tag> {
+fn may_be_tag(tag_char: Local<char>, local_src: Local<SourceEvent>, mut raw: Vec<Local<SourceEvent>>, props: &TaggerProperties) -> NextResult<TaggerState,Tag> {
     raw.push(local_src);
+    let stray = || warn(&props.diagnostics,tag_char.local(()),|| Warning::StrayLessThan);
     Ok(match *local_src.data() {
         SourceEvent::Char(lc) => {
             let local_char = local_src.local(lc);
             match lc {
                 '<' => {
+                    stray();
                     Next::empty()
                         .with_state(TaggerState::MayBeTag{ tag_char: local_char, raw: vec![local_src] })
                         .with_event(tag_char.map(|c| ParserEvent::Char(c)))
@@ -621,17 +665,23 @@ fn may_be_tag(tag_char: Local<char>, local_src: Local<SourceEvent>, mut raw: Vec
                         raw,
                     })
                 },
-                _ => Next::empty()
-                    .with_event(tag_char.map(|c| ParserEvent::Char(c)))
-                    .with_event(local_char.map(|c| ParserEvent::Char(c))),
+                _ => {
+                    stray();
+                    Next::empty()
+                        .with_event(tag_char.map(|c| ParserEvent::Char(c)))
+                        .with_event(local_char.map(|c| ParserEvent::Char(c)))
+                },
             }
         },
         SourceEvent::Breaker(b) => match b {
             Breaker::None => Next::empty()
                 .with_state(TaggerState::MayBeTag{ tag_char, raw }),
-            _ => Next::empty()
-                .with_event(tag_char.map(|c| ParserEvent::Char(c)))
-                .with_event(local_src.local(ParserEvent::Breaker(b))),
+            _ => {
+                stray();
+                Next::empty()
+                    .with_event(tag_char.map(|c| ParserEvent::Char(c)))
+                    .with_event(local_src.local(ParserEvent::Breaker(b)))
+            },
         },
     })
 }
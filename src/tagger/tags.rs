@@ -4,11 +4,13 @@ use crate::{
     Snip,Breaker,Local,SourceEvent,
 };
 
-#[derive(Debug,Eq,PartialEq)]
+#[derive(Debug,Clone,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub struct Tag {
     pub name: TagName,
     //pub breaker: Breaker,
     pub closing: Closing,
+    #[cfg_attr(feature = "serde", serde(with = "crate::checkpoint::opt_vec"))]
     pub attributes: OptVec<(String,Option<Snip>)>, // snip in raw
     pub begin: Local<()>,
     pub end: Local<()>,
@@ -224,6 +226,7 @@ Defines an embedded applet
 */
 
 #[derive(Debug,Clone,Copy,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum Closing {
     Void,
     Open,
@@ -231,6 +234,7 @@ pub enum Closing {
 }
 
 #[derive(Debug,Clone,Copy,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum SpecTag {
     Slash,
     Excl,
@@ -238,6 +242,7 @@ pub enum SpecTag {
 }
 
 #[derive(Debug,Clone,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
 pub enum TagName {
     // Basic HTML
     Html,
@@ -579,6 +584,16 @@ impl TagName {
         }
     }
 
+    // end tag may be omitted (closed implicitly by a parent or a sibling)
+    pub(crate) fn is_optional_end(&self) -> bool {
+        matches!(self,
+            TagName::Html | TagName::Head | TagName::Body |
+            TagName::P | TagName::Li | TagName::Dt | TagName::Dd | TagName::Rp | TagName::Rt |
+            TagName::Optgroup | TagName::Option |
+            TagName::Caption | TagName::Colgroup | TagName::Thead | TagName::Tbody | TagName::Tfoot |
+            TagName::Tr | TagName::Td | TagName::Th)
+    }
+
     pub(crate) fn is_common(&self) -> bool {
         match self {
             TagName::H1 |
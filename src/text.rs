@@ -0,0 +1,299 @@
+use crate::{
+    Snip, Local, Localize,
+    Source, SourceEvent, Breaker,
+    Error,
+};
+
+/*
+
+   Collecting a Source into a String with a reverse offset map
+
+   Every output piece (a char or a rendered breaker) is mapped to the Local it came from.
+   Pieces are stored as segments:
+
+      exact: a run of chars equal in length (chars, bytes, utf16) to their sources, contiguous
+             in the output and in the source (plain text) and of the same utf-8 width,
+             any sub-range maps to the exact source sub-range (offsets are computed, not scanned)
+
+      inexact: entity expansions, merged breakers, case folding/normalization results, etc.
+             any sub-range maps to the whole source span of the segment
+
+   Breakers are rendered with configurable text, an empty text drops the breaker.
+
+*/
+
+#[derive(Debug,Clone)]
+pub struct TextBuilder {
+    breakers: [String; 7],
+}
+impl Default for TextBuilder {
+    fn default() -> TextBuilder {
+        TextBuilder::new()
+    }
+}
+impl TextBuilder {
+    pub fn new() -> TextBuilder {
+        TextBuilder {
+            breakers: [
+                String::new(),      // None
+                " ".to_string(),    // Space
+                " ".to_string(),    // Word
+                "\n".to_string(),   // Line
+                " ".to_string(),    // Sentence
+                "\n\n".to_string(), // Paragraph
+                "\n\n".to_string(), // Section
+            ],
+        }
+    }
+    pub fn with_breaker(mut self, breaker: Breaker, text: &str) -> TextBuilder {
+        self.breakers[breaker_index(breaker)] = text.to_string();
+        self
+    }
+
+    pub fn process<S: Source>(&self, mut src: S) -> Result<ProcessedText,Error> {
+        let mut text = ProcessedText {
+            text: String::new(),
+            chars: 0,
+            segments: Vec::new(),
+        };
+        while let Some(local_se) = src.next_char()? {
+            let (local,se) = local_se.into_inner();
+            match se {
+                SourceEvent::Char(c) => text.push_char(c,local)?,
+                SourceEvent::Breaker(b) => text.push_str(&self.breakers[breaker_index(b)],local)?,
+            }
+        }
+        Ok(text)
+    }
+}
+
+fn breaker_index(b: Breaker) -> usize {
+    match b {
+        Breaker::None => 0,
+        Breaker::Space => 1,
+        Breaker::Word => 2,
+        Breaker::Line => 3,
+        Breaker::Sentence => 4,
+        Breaker::Paragraph => 5,
+        Breaker::Section => 6,
+    }
+}
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+struct Segment {
+    chars: Snip,
+    bytes: Snip,
+    source: Local<()>,
+    exact: bool,
+    width: usize, // utf-8 bytes of every char of an exact segment
+}
+
+#[derive(Debug,Clone)]
+pub struct ProcessedText {
+    text: String,
+    chars: usize,
+    segments: Vec<Segment>,
+}
+impl ProcessedText {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub fn into_string(self) -> String {
+        self.text
+    }
+    pub fn chars(&self) -> usize {
+        self.chars
+    }
+    pub fn segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    fn push_char(&mut self, c: char, local: Local<()>) -> Result<(),Error> {
+        let exact = (local.chars().length == 1) && (local.bytes().length == c.len_utf8()) && (local.utf16().length == c.len_utf16());
+        if let Some(last) = self.segments.last_mut() {
+            let src = last.source;
+            let contiguous = exact && last.exact && (last.width == c.len_utf8()) &&
+                (src.chars().offset + src.chars().length == local.chars().offset) &&
+                (src.bytes().offset + src.bytes().length == local.bytes().offset) &&
+                (src.utf16().offset + src.utf16().length == local.utf16().offset);
+            if contiguous || (!exact && !last.exact && (src == local)) {
+                if contiguous {
+                    last.source = Local::from_segment(src,local)?;
+                }
+                last.chars.length += 1;
+                last.bytes.length += c.len_utf8();
+                self.text.push(c);
+                self.chars += 1;
+                return Ok(());
+            }
+        }
+        self.segments.push(Segment {
+            chars: Snip { offset: self.chars, length: 1 },
+            bytes: Snip { offset: self.text.len(), length: c.len_utf8() },
+            source: local,
+            exact,
+            width: c.len_utf8(),
+        });
+        self.text.push(c);
+        self.chars += 1;
+        Ok(())
+    }
+
+    fn push_str(&mut self, s: &str, local: Local<()>) -> Result<(),Error> {
+        if s.is_empty() {
+            return Ok(());
+        }
+        let chars = s.chars().count();
+        self.segments.push(Segment {
+            chars: Snip { offset: self.chars, length: chars },
+            bytes: Snip { offset: self.text.len(), length: s.len() },
+            source: local,
+            exact: false,
+            width: 0,
+        });
+        self.text.push_str(s);
+        self.chars += chars;
+        Ok(())
+    }
+
+    // source of the output char at (segment, char offset, byte offset)
+    fn source_at(&self, idx: usize, char_offset: usize, byte_offset: usize) -> Local<()> {
+        let seg = &self.segments[idx];
+        if !seg.exact {
+            return seg.source;
+        }
+        let idx = char_offset - seg.chars.offset;
+        let utf16 = if seg.width == 4 { 2 } else { 1 };
+        let src = seg.source;
+        ().localize_utf16(
+            Snip { offset: src.chars().offset + idx, length: 1 },
+            Snip { offset: src.bytes().offset + (byte_offset - seg.bytes.offset), length: seg.width },
+            Snip { offset: src.utf16().offset + idx * utf16, length: utf16 },
+        )
+    }
+
+    // (segment, byte offset) of the output char
+    fn char_at(&self, char_offset: usize) -> Option<(usize,usize)> {
+        let idx = match self.segments.binary_search_by(|seg| seg.chars.offset.cmp(&char_offset)) {
+            Ok(idx) => idx,
+            Err(0) => return None,
+            Err(idx) => idx - 1,
+        };
+        let seg = &self.segments[idx];
+        let i = char_offset - seg.chars.offset;
+        if i >= seg.chars.length {
+            return None;
+        }
+        let byte = match seg.exact {
+            true => i * seg.width,
+            false => self.text[seg.bytes.offset .. seg.bytes.offset + seg.bytes.length].char_indices().nth(i)?.0,
+        };
+        Some((idx,seg.bytes.offset + byte))
+    }
+
+    // (segment, char offset, byte offset) of the output char containing the byte
+    fn byte_at(&self, byte_offset: usize) -> Option<(usize,usize,usize)> {
+        if byte_offset >= self.text.len() {
+            return None;
+        }
+        let idx = match self.segments.binary_search_by(|seg| seg.bytes.offset.cmp(&byte_offset)) {
+            Ok(idx) => idx,
+            Err(0) => return None,
+            Err(idx) => idx - 1,
+        };
+        let seg = &self.segments[idx];
+        if seg.exact {
+            let i = (byte_offset - seg.bytes.offset) / seg.width;
+            return Some((idx,seg.chars.offset + i,seg.bytes.offset + i * seg.width));
+        }
+        self.text[seg.bytes.offset .. seg.bytes.offset + seg.bytes.length]
+            .char_indices()
+            .enumerate()
+            .take_while(|(_,(b,_))| (seg.bytes.offset + b) <= byte_offset)
+            .last()
+            .map(|(i,(b,_))| (idx,seg.chars.offset + i,seg.bytes.offset + b))
+    }
+
+    // original span of the char range of the processed text
+    pub fn source_of_chars(&self, chars: Snip) -> Option<Local<()>> {
+        let (begin_idx,begin_byte) = self.char_at(chars.offset)?;
+        let begin = self.source_at(begin_idx,chars.offset,begin_byte);
+        if chars.length == 0 {
+            return Some(begin.into_position());
+        }
+        let last = chars.offset + chars.length - 1;
+        let (end_idx,end_byte) = self.char_at(last)?;
+        let end = self.source_at(end_idx,last,end_byte);
+        Local::from_segment(begin,end).ok()
+    }
+
+    // original span of the byte range of the processed text (partial chars are included)
+    pub fn source_of_bytes(&self, bytes: Snip) -> Option<Local<()>> {
+        let (begin_idx,begin_char,begin_byte) = self.byte_at(bytes.offset)?;
+        let begin = self.source_at(begin_idx,begin_char,begin_byte);
+        if bytes.length == 0 {
+            return Some(begin.into_position());
+        }
+        let (end_idx,end_char,end_byte) = self.byte_at(bytes.offset + bytes.length - 1)?;
+        let end = self.source_at(end_idx,end_char,end_byte);
+        Local::from_segment(begin,end).ok()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn html() {
+        let html = "<p>Привет&nbsp;&amp; мир</p>\n<p>Straße</p>";
+        let src = html.into_source()
+            .pipe(tagger::Builder::new().create().into_breaker())
+            .pipe(entities::Builder::new().create().into_piped())
+            .into_separator()
+            .merge_separators()
+            .case_fold();
+        let text = TextBuilder::new()
+            .with_breaker(Breaker::Space,"_")
+            .process(src)
+            .unwrap();
+
+        assert_eq!(text.text(),"\n\nпривет_&_мир\n\nstrasse\n\n");
+        assert_eq!(text.chars(),25);
+        assert_eq!(text.segments(),11);
+
+        let original = |chars: Snip| {
+            let local = text.source_of_chars(chars).unwrap();
+            html[local.bytes().offset .. local.bytes().offset + local.bytes().length].to_string()
+        };
+        assert_eq!(original(Snip { offset: 4, length: 4 }),"ивет");
+        assert_eq!(original(Snip { offset: 7, length: 5 }),"т&nbsp;&amp; м");
+        assert_eq!(original(Snip { offset: 20, length: 1 }),"ß");
+        assert_eq!(original(Snip { offset: 16, length: 9 }),"Straße</p>");
+        assert_eq!(original(Snip { offset: 13, length: 2 }),"р</p>\n<p>");
+        assert_eq!(text.source_of_chars(Snip { offset: 25, length: 1 }),None);
+
+        let local = text.source_of_chars(Snip { offset: 4, length: 4 }).unwrap();
+        assert_eq!(local.chars(),Snip { offset: 5, length: 4 });
+        assert_eq!(local.utf16(),Snip { offset: 5, length: 4 });
+        assert_eq!(text.source_of_bytes(Snip { offset: 6, length: 8 }),Some(local));
+        assert_eq!(text.source_of_bytes(Snip { offset: 7, length: 6 }),Some(local));
+        assert_eq!(text.source_of_bytes(Snip { offset: 40, length: 1 }),None);
+    }
+
+    #[test]
+    fn widths() {
+        let plain = "ab Ёж💯💯 c".repeat(1000);
+        let text = TextBuilder::new().process(plain.as_str().into_source()).unwrap();
+        assert_eq!(text.text(),plain);
+        assert_eq!(text.segments(),3001);
+
+        let local = text.source_of_chars(Snip { offset: 8103, length: 5 }).unwrap();
+        assert_eq!(local.chars(),Snip { offset: 8103, length: 5 });
+        assert_eq!(local.bytes(),Snip { offset: 15303, length: 13 });
+        assert_eq!(local.utf16(),Snip { offset: 9903, length: 7 });
+        assert_eq!(text.source_of_bytes(Snip { offset: 15304, length: 12 }),Some(local));
+        assert_eq!(text.source_of_bytes(Snip { offset: 15317, length: 0 }).map(|local| local.chars()),Some(Snip { offset: 8109, length: 0 }));
+    }
+}
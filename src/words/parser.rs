@@ -0,0 +1,148 @@
+use crate::{
+    ParserResult,
+    Source,
+    Parser, Runtime,
+    RuntimeState, Checkpoint,
+};
+
+use super::{
+    state::{WordState,Word},
+};
+
+/*
+
+   Word boundaries (UAX #29): https://www.unicode.org/reports/tr29/#Word_Boundaries
+
+   Chars are grouped into words (spaces and punctuation are words too), each word is a single ParserEvent::Parsed(Word),
+   its Local spans all the chars of the word (entities and tags included: "don&#39;t", "pre<wbr>fix").
+   Breakers are hard boundaries and are passed as they are, except Breaker::None, which is skipped.
+
+*/
+
+#[derive(Debug,Clone,Default)]
+pub struct Builder {
+
+}
+impl Builder {
+    pub fn new() -> Builder {
+        Builder{
+          
+        }
+    }
+    pub fn create(self) -> Words {
+        Words(Runtime::new(()))
+    }
+}
+
+
+pub struct Words(Runtime<WordState,Word,()>);
+
+impl Parser for Words {
+    type Data = Word;
+
+    fn next_event<S: Source>(&mut self, src: &mut S) -> ParserResult<Word> {
+        self.0.next_event(src)
+    }
+}
+
+#[derive(Debug,Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct WordCheckpoint(RuntimeState<WordState,Word>);
+
+impl Checkpoint for Words {
+    type State = WordCheckpoint;
+
+    fn checkpoint(&self) -> Option<WordCheckpoint> {
+        self.0.checkpoint().map(WordCheckpoint)
+    }
+    fn restore(&mut self, state: WordCheckpoint) {
+        self.0.restore(state.0);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use super::*;
+    use super::super::state::WordKind;
+
+    fn words<S: Source>(mut src: S) -> Vec<(String,WordKind,usize,usize)> {
+        let mut parser = Builder::new().create();
+        let mut res = Vec::new();
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            if let ParserEvent::Parsed(word) = local_event.data() {
+                res.push((word.text.clone(),word.kind,local_event.chars().offset,local_event.chars().length));
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn basic() {
+        let w = |text: &str, kind, offset, length| (text.to_string(),kind,offset,length);
+        assert_eq!(words("Don't pay $3.14, mp3 👍🏽!".into_source()),vec![
+            w("Don't",WordKind::Letter,0,5),
+            w(" ",WordKind::Space,5,1),
+            w("pay",WordKind::Letter,6,3),
+            w(" ",WordKind::Space,9,1),
+            w("$",WordKind::Symbol,10,1),
+            w("3.14",WordKind::Number,11,4),
+            w(",",WordKind::Punct,15,1),
+            w(" ",WordKind::Space,16,1),
+            w("mp3",WordKind::Mixed,17,3),
+            w(" ",WordKind::Space,20,1),
+            w("👍🏽",WordKind::Emoji,21,2),
+            w("!",WordKind::Punct,23,1),
+        ]);
+    }
+
+    #[test]
+    fn markup() {
+        let w = |text: &str, kind, offset, length| (text.to_string(),kind,offset,length);
+        let src = "<p>don&#39;t pre<wbr>fix</p>".into_source()
+            .pipe(tagger::Builder::new().create().into_breaker())
+            .pipe(entities::Builder::new().create().into_piped());
+        assert_eq!(words(src),vec![
+            w("don't",WordKind::Letter,3,9),
+            w(" ",WordKind::Space,12,1),
+            w("prefix",WordKind::Letter,13,11),
+        ]);
+    }
+
+    #[test]
+    fn long_token() {
+        let text = "QUJD".repeat(5000);
+        let res = words(text.as_str().into_source());
+        assert!(res.iter().all(|(_,kind,_,length)| (*kind == WordKind::Letter) && (*length <= 256)));
+        assert_eq!(res.iter().map(|(word,..)| word.as_str()).collect::<String>(),text);
+        assert_eq!(res.iter().map(|(_,_,_,length)| length).sum::<usize>(),text.len());
+    }
+
+    #[test]
+    fn breakers() {
+        let mut src = "ab".into_source()
+            .chain(Breaker::Word.into_source_as(" "))
+            .chain("cd".into_source());
+        let mut parser = Builder::new().create();
+
+        let mut res_iter = [
+            ParserEvent::Parsed(Word { text: "ab".to_string(), kind: WordKind::Letter }).localize(Snip { offset: 0, length: 2 },Snip { offset: 0, length: 2 }),
+            ParserEvent::Breaker(Breaker::Word).localize(Snip { offset: 2, length: 1 },Snip { offset: 2, length: 1 }),
+            ParserEvent::Parsed(Word { text: "cd".to_string(), kind: WordKind::Letter }).localize(Snip { offset: 3, length: 2 },Snip { offset: 3, length: 2 }),
+        ].into_iter();
+
+        while let Some(local_event) = parser.next_event(&mut src).unwrap() {
+            match res_iter.next() {
+                Some(ev) => {
+                    println!("Parser: {:?}",local_event);
+                    println!("Result: {:?}",ev);
+                    assert_eq!(local_event,ev);
+                },
+                None => {
+                    panic!("parser has more events then test result");
+                },
+            }
+        }
+    }
+}
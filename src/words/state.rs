@@ -0,0 +1,123 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_properties::{
+    UnicodeGeneralCategory, GeneralCategoryGroup,
+    UnicodeEmoji,
+};
+
+use crate::{
+    Local, ParserEvent,
+    NextResult, Next, StateMachine,
+    SourceEvent, Breaker,
+};
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub enum WordKind {
+    Letter,     // "word", "can't", "日"
+    Number,     // "42", "3.14", "1,000"
+    Mixed,      // letters and digits: "mp3", "3rd"
+    Punct,
+    Symbol,
+    Emoji,      // emoji sequences: "👍🏽", "👨‍👩‍👧", flags
+    Space,
+    Other,
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub struct Word {
+    pub text: String,
+    pub kind: WordKind,
+}
+
+fn kind(text: &str) -> WordKind {
+    if text.chars().all(char::is_whitespace) {
+        return WordKind::Space;
+    }
+    let first = match text.chars().next() {
+        Some(c) => c,
+        None => return WordKind::Other,
+    };
+    if !first.is_ascii() && first.is_emoji_char() && (first.general_category_group() == GeneralCategoryGroup::Symbol) {
+        return WordKind::Emoji;
+    }
+    match (text.chars().any(char::is_alphabetic),text.chars().any(char::is_numeric)) {
+        (true,false) => WordKind::Letter,
+        (false,true) => WordKind::Number,
+        (true,true) => WordKind::Mixed,
+        (false,false) => match first.general_category_group() {
+            GeneralCategoryGroup::Punctuation => WordKind::Punct,
+            GeneralCategoryGroup::Symbol => WordKind::Symbol,
+            _ => WordKind::Other,
+        },
+    }
+}
+
+/*
+
+   Boundaries (UAX #29) look ahead no further than the next segment ("a.b", "3.14"),
+   so all but the last two segments of the buffered chars are final.
+
+   The buffer is re-segmented on every char, so it is kept under MAX_WORD chars: a longer buffer is cut
+   at the last segment boundary, a single longer segment (base64, minified code) is cut into MAX_WORD-char words.
+
+*/
+
+const MAX_WORD: usize = 256;
+
+#[derive(Debug,Clone,Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize,serde::Deserialize))]
+pub(in super) struct WordState {
+    chars: Vec<Local<char>>,
+}
+impl WordState {
+    // lengths (in chars) of the word segments of the buffer
+    fn segments(&self) -> Vec<usize> {
+        let text: String = self.chars.iter().map(|c| *c.data()).collect();
+        text.split_word_bounds().map(|s| s.chars().count()).collect()
+    }
+    // emits all segments but the last `keep`
+    fn flush(mut self, keep: usize, mut next: Next<WordState,Word>) -> NextResult<WordState,Word> {
+        let segments = self.segments();
+        let long = self.chars.len() >= MAX_WORD;
+        let ready = match segments.len().saturating_sub(if long { keep.min(1) } else { keep }) {
+            0 if long => segments.len(),
+            ready => ready,
+        };
+        let rest = self.chars.split_off(segments[.. ready].iter().sum());
+        let mut chars = self.chars.into_iter();
+        for len in &segments[.. ready] {
+            let segment: Vec<Local<char>> = chars.by_ref().take(*len).collect();
+            let text: String = segment.iter().map(|c| *c.data()).collect();
+            if let (Some(first),Some(last)) = (segment.first(),segment.last()) {
+                let local = Local::from_segment(*first,*last)?;
+                next.push_event(local.local(ParserEvent::Parsed(Word { kind: kind(&text), text })));
+            }
+        }
+        Ok(next.with_state(WordState { chars: rest }))
+    }
+}
+
+impl StateMachine for WordState {
+    type Context = ();
+    type Data = Word;
+
+    fn eof(self, _props: &()) -> NextResult<WordState,Word> {
+        self.flush(0,Next::empty())
+    }
+    fn next_state(mut self, local_src: Local<SourceEvent>, _props: &()) -> NextResult<WordState,Word> {
+        match *local_src.data() {
+            SourceEvent::Char(c) => {
+                self.chars.push(local_src.local(c));
+                self.flush(2,Next::empty())
+            },
+            // zero-width, not a boundary ("<wbr>")
+            SourceEvent::Breaker(Breaker::None) => Ok(Next::empty().with_state(self)),
+            SourceEvent::Breaker(b) => {
+                let mut next = self.flush(0,Next::empty())?;
+                next.push_event(local_src.local(ParserEvent::Breaker(b)));
+                Ok(next)
+            },
+        }
+    }
+}